@@ -0,0 +1,92 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use client::game::{board::Board, Slot};
+use client::{Move, Player, Side, Turn};
+
+/// Every Turn of a game that fills a `size` x `size` Board row by row,
+/// alternating Players and the side they insert from.
+fn full_game(size: usize) -> Vec<Turn> {
+    let mut source = Player::First;
+    let mut turns = Vec::with_capacity(size * size);
+
+    for row in 0..size {
+        for col in 0..size {
+            let side = if col % 2 == 0 { Side::Left } else { Side::Right };
+            turns.push(Turn {
+                source,
+                mov: Move { side, row },
+            });
+            source = !source;
+        }
+    }
+
+    turns
+}
+
+/// A Board whose first row has every Slot but one filled.
+fn deep_row(width: usize) -> Board {
+    let mut board = Board::new(1, width);
+
+    for _ in 0..width - 1 {
+        board.insert_from_right(0, Slot::X).unwrap();
+    }
+
+    board
+}
+
+fn win_detection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_game_over");
+
+    for &size in [7, 15].iter() {
+        let board = Board::from_turns(size, size, &full_game(size)).unwrap();
+        let (row, col) = (size / 2, size / 2);
+        let slot = *board.get(row, col).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("full_board", size), &board, |b, board| {
+            b.iter(|| board.is_game_over(row, col, &slot))
+        });
+    }
+
+    group.finish();
+}
+
+fn insertion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+
+    for &width in [7, 64, 512].iter() {
+        group.bench_with_input(BenchmarkId::new("from_left", width), &width, |b, &width| {
+            b.iter_batched(
+                || deep_row(width),
+                |mut board| board.insert_from_left(0, Slot::O),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_with_input(BenchmarkId::new("from_right", width), &width, |b, &width| {
+            b.iter_batched(
+                || deep_row(width),
+                |mut board| board.insert_from_right(0, Slot::O),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn replay(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_turns");
+
+    for &size in [7, 15].iter() {
+        let turns = full_game(size);
+
+        group.bench_with_input(BenchmarkId::new("full_game", size), &turns, |b, turns| {
+            b.iter(|| Board::from_turns(size, size, turns))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, win_detection, insertion, replay);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+use rand::seq::IndexedRandom;
+
+use crate::{game::board::Board, Move, Player};
+
+/// Decides the Moves of a computer-controlled Player.
+pub trait Strategy {
+    /// Choose a Move for the given Player to make on the given Board.
+    /// Returns `None` if there is no legal Move left to make.
+    fn choose_move(&mut self, board: &Board, player: Player) -> Option<Move>;
+}
+
+/// Plays a uniformly random legal Move.
+#[derive(Debug, Default)]
+pub struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn choose_move(&mut self, board: &Board, _player: Player) -> Option<Move> {
+        board.available_moves().choose(&mut rand::rng()).copied()
+    }
+}
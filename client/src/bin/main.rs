@@ -1,27 +1,192 @@
-use futures::StreamExt;
+use futures::sink::SinkExt;
+use std::io::{self, prelude::*};
+use std::path::Path;
+use std::time::Duration;
 
 use structopt::StructOpt;
-use tokio::net::TcpStream;
-use tokio_util::codec::{Framed, LinesCodec};
+use tokio::time::sleep;
 
-use client::{error::ClientError, process, session::Session, Client, Connection, Params, Response};
+use client::input::{MoveSource, Scripted, Stdin};
+use client::transcript::Transcript;
+use client::{
+    ai::RandomStrategy,
+    check_protocol_version,
+    error::ClientError,
+    game::{validate_turns, BoardStyle},
+    history, process,
+    replay::Replay,
+    session::Session,
+    Client, Connection, Endpoint, Params, Player, ReplayParams, Request, Response, Ruleset, Turn,
+};
 
 #[tokio::main]
-async fn main() -> Result<(), ClientError> {
-    let Client::Connect(Params { addr }) = Client::from_args();
+async fn main() {
+    let result = match Client::from_args() {
+        Client::Connect(params) => connect(params).await,
+        Client::Replay(params) => replay(params).await,
+    };
+
+    // shown as the message the Player should read, not as a debug dump
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Step through a recorded game.
+async fn replay(
+    ReplayParams {
+        path,
+        height,
+        width,
+        replay_speed,
+        no_unicode,
+    }: ReplayParams,
+) -> Result<(), ClientError> {
+    let turns: Vec<Turn> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    validate_turns(height, width, &turns)?;
+
+    let mut replay = Replay::new(height, width, turns);
+    replay.board_style = BoardStyle::detect(no_unicode);
+    replay.run(Duration::from_millis(replay_speed)).await
+}
+
+/// Play a game against the computer without connecting to a server.
+fn play_offline(
+    rules_text: String,
+    confirm_moves: bool,
+    echo_moves: bool,
+    board_style: BoardStyle,
+    board_from_file: Option<&Path>,
+) -> Result<(), ClientError> {
+    let mut session = Session::new(Player::First, Ruleset::default());
+    session.rules_text = rules_text;
+    session.confirm_moves = confirm_moves;
+    session.echo_moves = echo_moves;
+    session.board_style = board_style;
+
+    if let Some(path) = board_from_file {
+        session.start_from_file(path)?;
+    }
+
+    session
+        .play_offline(&mut RandomStrategy, &mut Stdin)
+        .map(|_| ())
+}
+
+/// Ask whether to fall back to playing against the computer.
+fn offer_offline() -> Result<bool, ClientError> {
+    print!("Play against the computer instead? [y/N] ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Connect to a server and play a game.
+async fn connect(
+    Params {
+        addr,
+        unix_socket,
+        last,
+        connect_retries,
+        retry_delay_ms,
+        offline,
+        board_from_file,
+        reconnect_attempts,
+        lang,
+        rules_text,
+        self_play,
+        confirm_moves,
+        auto_move,
+        transcript,
+        no_unicode,
+        echo_moves,
+    }: Params,
+) -> Result<(), ClientError> {
+    let board_style = BoardStyle::detect(no_unicode);
+
+    let rules_text = match rules_text {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => lang.rules_text().to_string(),
+    };
+
+    if offline {
+        return play_offline(
+            rules_text,
+            confirm_moves,
+            echo_moves,
+            board_style,
+            board_from_file.as_deref(),
+        );
+    }
+
+    let addr = if last {
+        history::last().ok_or_else(|| {
+            ClientError::ConnectionError(String::from(
+                "There's no recently used server to connect to.",
+            ))
+        })?
+    } else {
+        addr
+    };
+
+    let server = match unix_socket {
+        Some(path) => Endpoint::Unix(path),
+        None => Endpoint::Tcp(addr),
+    };
+
+    let mut attempts = 0;
+    let mut connection = loop {
+        match Connection::connect_to(server.clone()).await {
+            Ok(connection) => break connection,
+            Err(e) if attempts < connect_retries => {
+                attempts += 1;
+                eprintln!(
+                    "Couldn't connect to {} ({}), retrying ({}/{})...",
+                    server, e, attempts, connect_retries
+                );
+                sleep(Duration::from_millis(retry_delay_ms)).await;
+            }
+            Err(e) => {
+                eprintln!("Couldn't connect to {} ({}).", server, e);
+
+                if offer_offline()? {
+                    return play_offline(rules_text, confirm_moves, echo_moves, board_style, None);
+                }
 
-    let mut connection = match TcpStream::connect(addr).await {
-        Ok(stream) => Connection {
-            lines: Framed::new(stream, LinesCodec::new()),
-        },
-        Err(e) => return Err(ClientError::ConnectionError(e.to_string())),
+                return Err(e);
+            }
+        }
     };
+    connection.reconnect_attempts = reconnect_attempts;
 
-    println!("Client connected to server at {}", addr);
+    if let Some(path) = transcript {
+        connection.record_to(Transcript::open(&path)?);
+    }
 
-    let response = match connection.lines.next().await {
-        Some(Ok(resp)) => resp,
-        Some(Err(e)) => return Err(ClientError::ServerError(e.to_string())),
+    if let Endpoint::Tcp(addr) = server {
+        if let Err(e) = history::record(addr) {
+            eprintln!("Couldn't remember this server for next time: {}", e);
+        }
+    }
+
+    println!("Client connected to server at {}", server);
+
+    let request = if self_play {
+        Request::SelfPlay
+    } else {
+        Request::Join
+    };
+    connection
+        .lines
+        .send(serde_json::to_string(&request)?)
+        .await?;
+
+    let response = match connection.next_response().await? {
+        Some(response) => response,
         None => {
             return Err(ClientError::ServerError(String::from(
                 "No response from server.",
@@ -29,14 +194,48 @@ async fn main() -> Result<(), ClientError> {
         }
     };
 
-    let response: Response = serde_json::from_str(&response)?;
-
     let mut session = match response {
         Response::Welcome {
             player,
-            height,
-            width,
-        } => Session::new(player, height, width),
+            ruleset,
+            protocol_version,
+            server_name,
+            start_position,
+            reconnect_token,
+            best_of,
+            ..
+        } => {
+            check_protocol_version(protocol_version)?;
+
+            if let Some(name) = server_name {
+                println!("Connected to {}", name);
+            }
+            connection.reconnect_token = reconnect_token;
+
+            if let Some(secs) = ruleset.turn_timeout_secs {
+                println!("Each turn must be taken within {} seconds.", secs);
+            }
+            if let Some(games) = best_of {
+                println!("This is a best-of-{} match.", games);
+            }
+
+            let mut session = Session::new(player, ruleset);
+            session.rules_text = rules_text;
+            session.self_play = self_play;
+            session.confirm_moves = confirm_moves;
+            session.echo_moves = echo_moves;
+            session.board_style = board_style;
+            session.best_of = best_of;
+
+            if let Some(encoded) = start_position {
+                session.start_from(&encoded)?;
+            }
+
+            session
+        }
+        Response::GameInProgress => return Err(ClientError::GameInProgress),
+        Response::GameFull => return Err(ClientError::GameFull),
+        Response::Error { message, .. } => return Err(ClientError::ServerError(message)),
         _ => {
             return Err(ClientError::ServerError(String::from(
                 "Inappropriate response from server.",
@@ -44,11 +243,17 @@ async fn main() -> Result<(), ClientError> {
         }
     };
 
-    loop {
-        tokio::spawn(async move {
-            if let Err(e) = process(&mut session, &mut connection).await {
-                eprintln!("Error: {}", e);
-            }
-        });
+    // a bot has no one to ask, so it turns down anything it's asked
+    let mut source: Box<dyn MoveSource> = if auto_move {
+        session.auto_move = Some(Box::new(RandomStrategy));
+        Box::new(Scripted::default())
+    } else {
+        Box::new(Stdin)
+    };
+
+    if let Err(e) = process(&mut session, &mut connection, source.as_mut()).await {
+        eprintln!("Error: {}", e);
     }
+
+    Ok(())
 }
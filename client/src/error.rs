@@ -5,6 +5,16 @@ use std::io;
 use serde_json::Error as JsonError;
 use tokio_util::codec;
 
+use crate::{Move, Side};
+
+/// The message shown when a game can't accept any more players. Building
+/// with the `ascii-only` feature keeps it readable on terminals and log
+/// parsers that can't handle emoji.
+#[cfg(not(feature = "ascii-only"))]
+pub const GAME_FULL_MESSAGE: &str = "Game is at max capacity and can't accept any more players 😞";
+#[cfg(feature = "ascii-only")]
+pub const GAME_FULL_MESSAGE: &str = "Game is at max capacity and can't accept any more players :(";
+
 /// The error types that may arise while the game is running.
 #[derive(Debug)]
 pub enum ClientError {
@@ -12,12 +22,42 @@ pub enum ClientError {
     FullRow,
     /// Attempted to fetch a non-existent row.
     NonexistentRow,
+    /// Attempted to insert into a row from a side it can't be played from.
+    BlockedSide { row: usize, allowed: Side },
+    /// Attempted to fetch a non-existent column.
+    NonexistentColumn,
+    /// Attempted to take back a move from a spot that holds no piece.
+    NoPieceToUndo,
+    /// An encoded Board couldn't be decoded.
+    MalformedBoard,
     /// A player specified a move in an invalid format.
     InvalidMoveFormat,
     /// A player specified a side that is not valid.
     InvalidSide,
+    /// A Player took a Turn when it was the other Player's move.
+    OutOfTurn,
+    /// The Turn at the given position, counting from 1, of a recorded game
+    /// couldn't have been played.
+    IllegalTurn { turn: usize, source: Box<ClientError> },
+    /// The given Move, at the given position counting from 1, of a batch
+    /// being placed on the Board couldn't be played.
+    IllegalPlacement {
+        index: usize,
+        mov: Move,
+        source: Box<ClientError>,
+    },
     /// Can't join a game because it is already at capacity.
     GameFull,
+    /// Can't join a game because it has already started.
+    GameInProgress,
+    /// The server's board has different dimensions, as `(height, width)`,
+    /// from the one this client is playing on.
+    BoardMismatch {
+        server: (usize, usize),
+        client: (usize, usize),
+    },
+    /// The server speaks a different version of the protocol.
+    ProtocolMismatch { server: u32, client: u32 },
     /// There was an error reading or writing input.
     InputError { source: io::Error },
     /// An error occurred with the game server.
@@ -35,6 +75,15 @@ impl fmt::Display for ClientError {
         match self {
             ClientError::FullRow => write!(f, "Row is full. Please pick a different one."),
             ClientError::NonexistentRow => write!(f, "That row doesn't exist. Please pick a different one."),
+            ClientError::BlockedSide { row, allowed } => write!(
+                f,
+                "Row {} can only be played from the {}.",
+                row,
+                allowed.name()
+            ),
+            ClientError::NonexistentColumn => write!(f, "That column doesn't exist. Please pick a different one."),
+            ClientError::NoPieceToUndo => write!(f, "There's no piece there to take back."),
+            ClientError::MalformedBoard => write!(f, "The encoded board is malformed. Rows must be separated by '/' and contain only '_', 'X', 'O', or '#'."),
             ClientError::InputError { source } => write!(f, "There was an error reading/writing input: {}", source),
             ClientError::InvalidMoveFormat => write!(f, "Please specify your move with a number indicating the row and a letter indicating the side ('l' or 'r'), with no spaces in between them."),
             ClientError::InvalidSide => write!(f, "Please specify a side with a letter, 'l' or 'r'."),
@@ -50,10 +99,46 @@ impl fmt::Display for ClientError {
                 "An error occurred while encoding or decoding a line: {}",
                 source
             ),
-            ClientError::GameFull => write!(
+            ClientError::GameFull => write!(f, "{}", GAME_FULL_MESSAGE),
+            ClientError::GameInProgress => write!(f, "That game is already in progress, so there's no seat to join. You can still watch it as a spectator."),
+            ClientError::BoardMismatch { server, client } => write!(
                 f,
-                "Game is at max capacity and can't accept any more players 😞"
+                "The server's board is {}x{}, but this client's is {}x{}.",
+                server.0, server.1, client.0, client.1
             ),
+            ClientError::OutOfTurn => write!(f, "A player moved when it wasn't their turn."),
+            ClientError::IllegalTurn { turn, source } => write!(f, "Turn {} of the game is illegal: {}", turn, source),
+            ClientError::IllegalPlacement { index, mov, source } => write!(f, "Move {} {} of the batch couldn't be placed: {}", index, mov, source),
+            ClientError::ProtocolMismatch { server, client } => write!(
+                f,
+                "The server speaks protocol version {} but this client speaks version {}. Please use matching builds.",
+                server, client
+            ),
+        }
+    }
+}
+
+impl ClientError {
+    /// Whether this is a failure of the connection to the server that
+    /// reconnecting might get past.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Self::ConnectionError(_) => true,
+            Self::CodecError { source } => matches!(source, codec::LinesCodecError::Io(_)),
+            _ => false,
+        }
+    }
+
+    /// A short hint asking the Player to try again, for errors caused by a
+    /// Move they entered. Other errors have no hint and should be shown in
+    /// full.
+    pub fn retry_hint(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidMoveFormat => Some("Moves look like `2R` or `5l`. Try again."),
+            Self::InvalidSide => Some("The side must be `l` or `r`. Try again."),
+            Self::NonexistentRow => Some("There's no such row. Try again."),
+            Self::FullRow => Some("That row is full. Try another."),
+            _ => None,
         }
     }
 }
@@ -82,6 +167,8 @@ impl Error for ClientError {
             Self::InputError { source } => Some(source),
             Self::CodecError { source } => Some(source),
             Self::SerializationError { source } => Some(source),
+            Self::IllegalTurn { source, .. } => Some(source.as_ref()),
+            Self::IllegalPlacement { source, .. } => Some(source.as_ref()),
             _ => None,
         }
     }
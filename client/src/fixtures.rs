@@ -0,0 +1,38 @@
+use crate::{
+    Move, Player, Response, Ruleset, Side, Turn, PROTOCOL_MINOR_VERSION, PROTOCOL_VERSION,
+};
+
+/// A Turn in which the given Player inserts into the given Row from the
+/// given Side.
+pub fn sample_turn(source: Player, row: usize, side: Side) -> Turn {
+    Turn {
+        source,
+        mov: Move { side, row },
+    }
+}
+
+/// The Welcome a server speaking this client's protocol sends the given
+/// Player, for a game played under the default Ruleset.
+pub fn sample_welcome(player: Player) -> Response {
+    Response::Welcome {
+        player,
+        ruleset: Ruleset::default(),
+        protocol_version: PROTOCOL_VERSION,
+        protocol_minor_version: PROTOCOL_MINOR_VERSION,
+        server_name: None,
+        start_position: None,
+        reconnect_token: None,
+        best_of: None,
+    }
+}
+
+/// The acknowledgement of a Turn that landed at the given coordinates
+/// without ending the game.
+pub fn sample_ack(coordinate: (usize, usize), next_player: Player) -> Response {
+    Response::Acknowledged {
+        coordinate,
+        game_over: None,
+        next_player,
+        board: None,
+    }
+}
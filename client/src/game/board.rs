@@ -1,14 +1,26 @@
-use std::fmt;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
 
+use super::tracker::WinTracker;
 use super::*;
-use crate::error::ClientError;
+use crate::{error::ClientError, Move, Player, Ruleset, Side, Turn};
 
 /// Represents the game board.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Board {
-    pub rows: Vec<Row>,
+    rows: Vec<Row>,
     pub height: usize,
     pub width: usize,
+    /// How many consecutive pieces a Player needs to win.
+    pub win_length: usize,
+    /// Whether pieces fall to the bottom of their column once inserted.
+    pub gravity: bool,
+    /// Whether the first Player plays `O` and the second plays `X`.
+    pub swap_symbols: bool,
+    /// The only Side each of these Rows may be played from.
+    pub side_restrictions: HashMap<usize, Side>,
+    /// The coordinates of the most recently occupied spot, if any.
+    pub last_move: Option<(usize, usize)>,
 }
 
 impl fmt::Display for Board {
@@ -22,15 +34,408 @@ impl fmt::Display for Board {
 }
 
 impl Board {
-    /// Initializes a new Board with the specified height and width.
+    /// Render the Board like its `Display` output, but with the most
+    /// recently occupied spot marked with a `*`.
+    pub fn render_with_last_move(&self) -> String {
+        let mut rendered = String::new();
+
+        for (row_num, row) in self.rows.iter().enumerate() {
+            let _ = write!(rendered, "{} [ ", row_num);
+
+            for (col, slot) in row.0.iter().enumerate() {
+                let marker = if self.last_move == Some((row_num, col)) {
+                    '*'
+                } else {
+                    ' '
+                };
+
+                let _ = write!(rendered, "{}{}", slot, marker);
+            }
+
+            let _ = writeln!(rendered, "]");
+        }
+
+        rendered
+    }
+
+    /// Render the Board in the given style, with the most recently
+    /// occupied spot marked.
+    pub fn render(&self, style: BoardStyle) -> String {
+        match style {
+            BoardStyle::Plain => self.render_with_last_move(),
+            BoardStyle::BoxDrawing => self.to_ascii_art(),
+        }
+    }
+
+    /// Render the Board as a grid drawn with box-drawing characters, one
+    /// cell per spot and each Row labelled with its number, with the most
+    /// recently occupied spot bracketed.
+    pub fn to_ascii_art(&self) -> String {
+        let label_width = self.height.saturating_sub(1).to_string().len();
+
+        let border = |left: char, middle: char, right: char| {
+            let cells = vec!["───"; self.width].join(&middle.to_string());
+            format!("{:w$} {}{}{}\n", "", left, cells, right, w = label_width)
+        };
+
+        let mut rendered = border('┌', '┬', '┐');
+
+        for (row_num, row) in self.rows.iter().enumerate() {
+            if row_num > 0 {
+                rendered.push_str(&border('├', '┼', '┤'));
+            }
+
+            let _ = write!(rendered, "{:>w$} ", row_num, w = label_width);
+
+            for (col, slot) in row.0.iter().enumerate() {
+                let symbol = match slot {
+                    Slot::Blank => ' ',
+                    Slot::X => 'X',
+                    Slot::O => 'O',
+                    Slot::Blocked => '#',
+                };
+
+                if self.last_move == Some((row_num, col)) {
+                    let _ = write!(rendered, "│[{}]", symbol);
+                } else {
+                    let _ = write!(rendered, "│ {} ", symbol);
+                }
+            }
+
+            rendered.push_str("│\n");
+        }
+
+        rendered.push_str(&border('└', '┴', '┘'));
+        rendered
+    }
+
+    /// Render the Board as a standalone SVG image, with `X` pieces in red,
+    /// `O` pieces in gold, and blocked spots filled in grey.
+    pub fn to_svg(&self) -> String {
+        const CELL: usize = 40;
+
+        let (width, height) = (self.width * CELL, self.height * CELL);
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            width, height
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <rect width="{}" height="{}" fill="white" stroke="black"/>"#,
+            width, height
+        );
+
+        for (row_num, row) in self.rows.iter().enumerate() {
+            for (col, slot) in row.0.iter().enumerate() {
+                let (x, y) = (col * CELL, row_num * CELL);
+                let background = match slot {
+                    Slot::Blocked => "grey",
+                    _ => "none",
+                };
+
+                let _ = writeln!(
+                    svg,
+                    r#"  <rect x="{}" y="{}" width="{2}" height="{2}" fill="{3}" stroke="black"/>"#,
+                    x, y, CELL, background
+                );
+
+                let fill = match slot {
+                    Slot::X => "red",
+                    Slot::O => "gold",
+                    Slot::Blank | Slot::Blocked => continue,
+                };
+
+                let _ = writeln!(
+                    svg,
+                    r#"  <circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    x + CELL / 2,
+                    y + CELL / 2,
+                    CELL * 2 / 5,
+                    fill
+                );
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Initializes a new Board with the specified height and width, using
+    /// the default rules for everything else.
     pub fn new(height: usize, width: usize) -> Self {
+        Self::with_ruleset(&Ruleset {
+            height,
+            width,
+            ..Ruleset::default()
+        })
+    }
+
+    /// Initializes a new, empty Board played under the given Ruleset.
+    pub fn with_ruleset(ruleset: &Ruleset) -> Self {
+        let Ruleset { height, width, .. } = *ruleset;
+
         Self {
             rows: (0..height)
                 .map(|_| Row((0..width).map(|_| Slot::Blank).collect::<Vec<_>>()))
                 .collect::<Vec<_>>(),
             height,
             width,
+            win_length: ruleset.win_length,
+            gravity: ruleset.gravity,
+            swap_symbols: ruleset.swap_symbols,
+            side_restrictions: ruleset.side_restrictions.clone(),
+            last_move: None,
+        }
+    }
+
+    /// Initializes a new Board with the specified height and width where
+    /// the given coordinates are Blocked. Coordinates outside the Board
+    /// are ignored.
+    pub fn with_blocked(height: usize, width: usize, blocked: &[(usize, usize)]) -> Self {
+        let mut board = Self::new(height, width);
+
+        for &(row_num, col) in blocked {
+            if let Some(spot) = board.rows.get_mut(row_num).and_then(|row| row.0.get_mut(col)) {
+                *spot = Slot::Blocked;
+            }
+        }
+
+        board
+    }
+
+    /// Encode the Board compactly as its Rows separated by `/`, with each
+    /// Slot written as it is displayed, e.g. `X__/O#_/___`.
+    pub fn encode(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| row.0.iter().map(Slot::to_string).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Decode a Board from the format produced by `encode`.
+    pub fn decode(encoded: &str) -> Result<Self, ClientError> {
+        let rows = encoded
+            .trim()
+            .split('/')
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '_' => Ok(Slot::Blank),
+                        'X' => Ok(Slot::X),
+                        'O' => Ok(Slot::O),
+                        '#' => Ok(Slot::Blocked),
+                        _ => Err(ClientError::MalformedBoard),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Row)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = rows.first().map_or(0, Row::len);
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return Err(ClientError::MalformedBoard);
+        }
+
+        let mut board = Self::new(rows.len(), width);
+        board.rows = rows;
+
+        Ok(board)
+    }
+
+    /// A copy of the Board with left and right swapped. Since Slots are
+    /// inserted from either side of a Row, this is the Board's natural
+    /// symmetry: playing every Move from the `opposite` Side of the source
+    /// Board produces the mirrored Board, so any Row that may only be
+    /// played from one Side is only playable from the other.
+    pub fn mirror_horizontal(&self) -> Board {
+        let mut mirrored = self.clone();
+
+        for row in mirrored.rows.iter_mut() {
+            row.0.reverse();
+        }
+        for side in mirrored.side_restrictions.values_mut() {
+            *side = side.opposite();
+        }
+        mirrored.last_move = self
+            .last_move
+            .map(|(row_num, col)| (row_num, self.width - 1 - col));
+
+        mirrored
+    }
+
+    /// A copy of the Board with its Rows and columns swapped. Unlike
+    /// `mirror_horizontal`, this doesn't correspond to any way of playing
+    /// the game, but win detection is unaffected by it on square Boards.
+    pub fn transpose(&self) -> Board {
+        let mut transposed = self.clone();
+
+        transposed.rows = (0..self.width)
+            .map(|col| {
+                Row(self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(col))
+                    .copied()
+                    .collect())
+            })
+            .collect();
+        transposed.height = self.width;
+        transposed.width = self.height;
+        transposed.last_move = self.last_move.map(|(row_num, col)| (col, row_num));
+
+        transposed
+    }
+
+    /// The Slot the given Player's pieces are shown as.
+    pub fn slot_for(&self, player: Player) -> Slot {
+        player.symbol(self.swap_symbols)
+    }
+
+    /// Count the pieces the given Player has placed on the Board.
+    pub fn count(&self, player: Player) -> usize {
+        let slot = self.slot_for(player);
+
+        self.rows
+            .iter()
+            .flat_map(|row| row.0.iter())
+            .filter(|spot| **spot == slot)
+            .count()
+    }
+
+    /// Work out whose turn it is from how many pieces each Player has
+    /// placed, given which Player moved first. Returns `None` if the counts
+    /// are too far apart for the Players to have taken turns.
+    pub fn to_move(&self, first_player: Player) -> Option<Player> {
+        match self
+            .count(first_player)
+            .checked_sub(self.count(!first_player))
+        {
+            Some(0) => Some(first_player),
+            Some(1) => Some(!first_player),
+            _ => None,
+        }
+    }
+
+    /// The Player whose pieces are shown as the given Slot, if any.
+    pub fn owner(&self, slot: Slot) -> Option<Player> {
+        match slot {
+            Slot::Blank | Slot::Blocked => None,
+            _ if slot == self.slot_for(Player::First) => Some(Player::First),
+            _ => Some(Player::Second),
+        }
+    }
+
+    /// Returns whether every Row of the Board is full.
+    pub fn is_full(&self) -> bool {
+        self.rows.iter().all(Row::is_full)
+    }
+
+    /// Returns whether either Player could still win, i.e. whether some
+    /// line of `win_length` spots holds only Blank Slots and the pieces of
+    /// at most one Player.
+    pub fn can_anyone_win(&self) -> bool {
+        let length = self.win_length as isize;
+
+        let open = |row_num: isize, col: isize, d_row: isize, d_col: isize| {
+            let mut owner = None;
+
+            (0..length).all(|i| {
+                let (r, c) = (row_num + d_row * i, col + d_col * i);
+                if r < 0 || c < 0 {
+                    return false;
+                }
+
+                match self.get(r as usize, c as usize) {
+                    Some(Slot::Blank) => true,
+                    Some(slot @ (Slot::X | Slot::O)) => owner.get_or_insert(*slot) == slot,
+                    Some(Slot::Blocked) | None => false,
+                }
+            })
+        };
+
+        (0..self.height as isize).any(|row_num| {
+            (0..self.width as isize).any(|col| {
+                [(1, 0), (0, 1), (-1, 1), (1, 1)]
+                    .iter()
+                    .any(|&(d_row, d_col)| open(row_num, col, d_row, d_col))
+            })
+        })
+    }
+
+    /// Count the pieces either Player has placed on the Board.
+    pub fn count_pieces(&self) -> usize {
+        self.rows
+            .iter()
+            .flat_map(|row| row.0.iter())
+            .filter(|slot| matches!(slot, Slot::X | Slot::O))
+            .count()
+    }
+
+    /// Count the spots on the Board that can ever hold a piece, which is
+    /// all of them except the Blocked ones.
+    pub fn capacity(&self) -> usize {
+        self.rows
+            .iter()
+            .flat_map(|row| row.0.iter())
+            .filter(|slot| **slot != Slot::Blocked)
+            .count()
+    }
+
+    /// A Zobrist hash of the Board's contents, suitable for keying a
+    /// transposition table. Boards with the same contents always hash the
+    /// same, no matter the order their pieces were placed in.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for (row_num, row) in self.rows.iter().enumerate() {
+            for (col, slot) in row.0.iter().enumerate() {
+                hash ^= Self::zobrist_key(row_num, col, *slot);
+            }
         }
+
+        hash
+    }
+
+    /// The key a Slot at the given coordinates contributes to the Board's
+    /// Zobrist hash. XOR a Slot's old key out and its new key in to update a
+    /// hash incrementally as pieces are placed and taken back.
+    pub fn zobrist_key(row_num: usize, col: usize, slot: Slot) -> u64 {
+        // blank spots contribute nothing, so an empty Board hashes to zero
+        let piece = match slot {
+            Slot::Blank => return 0,
+            Slot::X => 1,
+            Slot::O => 2,
+            Slot::Blocked => 3,
+        };
+
+        // a fixed splitmix64 scramble of the spot stands in for a table of
+        // random keys, so hashes are the same from run to run
+        let mut key = ((row_num as u64) << 34 | (col as u64) << 2 | piece)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        key ^ (key >> 31)
+    }
+
+    /// Rebuild a Board of the specified height and width by replaying the
+    /// given Turns in order.
+    pub fn from_turns(height: usize, width: usize, turns: &[Turn]) -> Result<Self, ClientError> {
+        let mut board = Self::new(height, width);
+
+        for turn in turns {
+            board.apply_turn(turn)?;
+        }
+
+        Ok(board)
+    }
+
+    /// Iterate over the Rows of the Board, from the top down.
+    pub fn rows_iter(&self) -> impl Iterator<Item = &Row> {
+        self.rows.iter()
     }
 
     /// Try to fetch a reference to a specified Row.
@@ -55,81 +460,382 @@ impl Board {
         Ok(row)
     }
 
-    /// Insert the given Slot into the specified Row from the left.
-    /// Returns the coordinates of the spot that becomes occupied.
+    /// Fetch the Slot at the given coordinates, or `None` if they're off
+    /// the Board.
+    pub fn get(&self, row_num: usize, col: usize) -> Option<&Slot> {
+        self.rows.get(row_num)?.0.get(col)
+    }
+
+    /// Fetch the Slots of the specified column, ordered from the top Row to
+    /// the bottom one.
+    pub fn column(&self, col: usize) -> Result<Vec<&Slot>, ClientError> {
+        if col >= self.width {
+            return Err(ClientError::NonexistentColumn);
+        }
+
+        Ok(self.rows.iter().filter_map(|row| row.get(col)).collect())
+    }
+
+    /// Collect the coordinates of every diagonal on the Board, each ordered
+    /// from its top Row to its bottom one. The `\` diagonals come first,
+    /// followed by the `/` diagonals.
+    pub fn diagonals(&self) -> Vec<Vec<(usize, usize)>> {
+        let (height, width) = (self.height, self.width);
+        if height == 0 || width == 0 {
+            return Vec::new();
+        }
+
+        let count = height + width - 1;
+
+        // a `\` diagonal keeps `col - row` fixed, so offset it by the height
+        // to keep it from going negative
+        let falling = (0..count).map(|k| {
+            (0..height)
+                .filter_map(|row| (k + row).checked_sub(height - 1).map(|col| (row, col)))
+                .filter(|&(_, col)| col < width)
+                .collect::<Vec<_>>()
+        });
+
+        // a `/` diagonal keeps `row + col` fixed
+        let rising = (0..count).map(|k| {
+            (0..height)
+                .filter_map(|row| k.checked_sub(row).map(|col| (row, col)))
+                .filter(|&(_, col)| col < width)
+                .collect::<Vec<_>>()
+        });
+
+        falling.chain(rising).collect()
+    }
+
+    /// Returns whether the given Row may be played from the given Side.
+    pub fn side_allowed(&self, row_num: usize, side: Side) -> bool {
+        self.side_restrictions
+            .get(&row_num)
+            .is_none_or(|allowed| *allowed == side)
+    }
+
+    /// Fail with a `BlockedSide` error if the given Row can't be played
+    /// from the given Side.
+    fn check_side(&self, row_num: usize, side: Side) -> Result<(), ClientError> {
+        match self.side_restrictions.get(&row_num) {
+            Some(&allowed) if allowed != side => Err(ClientError::BlockedSide {
+                row: row_num,
+                allowed,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Insert the given Slot into the specified Row from the left, skipping
+    /// over any Blocked Slots. Returns the coordinates of the spot that
+    /// becomes occupied.
     pub fn insert_from_left(
         &mut self,
         row_num: usize,
         slot: Slot,
     ) -> Result<(usize, usize), ClientError> {
+        self.check_side(row_num, Side::Left)?;
         let row = self.try_get_row_mut(row_num)?;
 
-        for (col, mut spot) in row.0.iter().rev().enumerate() {
-            match spot {
-                Slot::Blank => {
-                    spot = &slot;
-                    return Ok((row_num, row.len() - col - 1));
-                }
-                _ => continue,
-            }
-        }
+        let col = match row.0.iter().rposition(|spot| *spot == Slot::Blank) {
+            Some(col) => col,
+            None => return Err(ClientError::FullRow),
+        };
+        row.0[col] = slot;
 
-        Err(ClientError::FullRow)
+        Ok(self.settle(row_num, col))
     }
 
-    /// Insert the given Slot into the specified Row from the right.
-    /// Returns the coordinates of the spot that becomes occupied.
+    /// Insert the given Slot into the specified Row from the right, skipping
+    /// over any Blocked Slots. Returns the coordinates of the spot that
+    /// becomes occupied.
     pub fn insert_from_right(
         &mut self,
         row_num: usize,
         slot: Slot,
     ) -> Result<(usize, usize), ClientError> {
+        self.check_side(row_num, Side::Right)?;
         let row = self.try_get_row_mut(row_num)?;
 
-        for (col, mut spot) in row.0.iter().enumerate() {
-            match spot {
-                Slot::Blank => {
-                    spot = &slot;
-                    return Ok((row_num, col));
-                }
-                _ => continue,
+        let col = match row.0.iter().position(|spot| *spot == Slot::Blank) {
+            Some(col) => col,
+            None => return Err(ClientError::FullRow),
+        };
+        row.0[col] = slot;
+
+        Ok(self.settle(row_num, col))
+    }
+
+    /// Insert the Slot belonging to the Turn's Player from the Turn's side.
+    /// Returns the coordinates of the spot that becomes occupied.
+    pub fn apply_turn(&mut self, turn: &Turn) -> Result<(usize, usize), ClientError> {
+        let slot = self.slot_for(turn.source);
+
+        match turn.mov.side {
+            Side::Left => self.insert_from_left(turn.mov.row, slot),
+            Side::Right => self.insert_from_right(turn.mov.row, slot),
+        }
+    }
+
+    /// Insert each given Slot from the side of its Move, in order, e.g. to
+    /// set up a puzzle or test position. Returns where each one landed, or
+    /// stops at the first that can't be placed, leaving the ones before it
+    /// on the Board.
+    pub fn place_many(
+        &mut self,
+        moves: &[(Move, Slot)],
+    ) -> Result<Vec<(usize, usize)>, ClientError> {
+        moves
+            .iter()
+            .enumerate()
+            .map(|(i, &(mov, slot))| {
+                let placed = match mov.side {
+                    Side::Left => self.insert_from_left(mov.row, slot),
+                    Side::Right => self.insert_from_right(mov.row, slot),
+                };
+
+                placed.map_err(|source| ClientError::IllegalPlacement {
+                    index: i + 1,
+                    mov,
+                    source: Box::new(source),
+                })
+            })
+            .collect()
+    }
+
+    /// Drop the Slot at the given coordinates to the bottom of its column
+    /// if gravity is on, and record where it comes to rest as the last move.
+    fn settle(&mut self, mut row_num: usize, col: usize) -> (usize, usize) {
+        if self.gravity {
+            while self.get(row_num + 1, col) == Some(&Slot::Blank) {
+                self.rows[row_num + 1].0[col] = self.rows[row_num].0[col];
+                self.rows[row_num].0[col] = Slot::Blank;
+                row_num += 1;
             }
         }
 
-        Err(ClientError::FullRow)
+        self.last_move = Some((row_num, col));
+        (row_num, col)
+    }
+
+    /// Put the given Slot at the given coordinates, regardless of what was
+    /// there before, and record it as the last move.
+    pub fn set_slot(&mut self, row_num: usize, col: usize, slot: Slot) -> Result<(), ClientError> {
+        let row = self.try_get_row_mut(row_num)?;
+
+        match row.0.get_mut(col) {
+            Some(spot) => *spot = slot,
+            None => return Err(ClientError::NonexistentColumn),
+        }
+        self.last_move = Some((row_num, col));
+
+        Ok(())
+    }
+
+    /// Clear the Slot at the given coordinates, taking back the move that
+    /// occupied it. Returns the Slot that was removed. Only a piece can be
+    /// taken back: a Blank or Blocked Slot is left as it is.
+    pub fn undo_move(&mut self, row_num: usize, col: usize) -> Result<Slot, ClientError> {
+        let row = self.try_get_row_mut(row_num)?;
+
+        let spot = match row.0.get_mut(col) {
+            Some(spot) => spot,
+            None => return Err(ClientError::NonexistentColumn),
+        };
+        if let Slot::Blank | Slot::Blocked = spot {
+            return Err(ClientError::NoPieceToUndo);
+        }
+        let slot = std::mem::replace(spot, Slot::Blank);
+        self.last_move = None;
+
+        Ok(slot)
     }
 
     /// Computes whether the game is finished or not, starting at the given row and column index.
+    /// A Blank or Blocked Slot never wins, so there's no winner for one.
     pub fn is_game_over(
         &self,
         row_num: usize,
         col: usize,
         slot: &Slot,
     ) -> Result<Option<Slot>, ClientError> {
-        if let Slot::Blank = slot {
-            panic!("Found a Blank Slot where there should not have been one.");
-        }
-
-        // traverse the board in all 8 directions
-        let search_results = vec![
-            self.recurse(slot, row_num, col, 1, Direction::North)
-                + self.recurse(slot, row_num, col, 1, Direction::South)
-                - 1,
-            self.recurse(slot, row_num, col, 1, Direction::East)
-                + self.recurse(slot, row_num, col, 1, Direction::West)
-                - 1,
-            self.recurse(slot, row_num, col, 1, Direction::NorthEast)
-                + self.recurse(slot, row_num, col, 1, Direction::SouthWest)
-                - 1,
-            self.recurse(slot, row_num, col, 1, Direction::NorthWest)
-                + self.recurse(slot, row_num, col, 1, Direction::SouthEast)
-                - 1,
+        if let Slot::Blank | Slot::Blocked = slot {
+            return Ok(None);
+        }
+
+        // traverse the board in all 8 directions, paired up into the four
+        // axes a line can run along
+        let axes = [
+            (Direction::North, Direction::South),
+            (Direction::East, Direction::West),
+            (Direction::NorthEast, Direction::SouthWest),
+            (Direction::NorthWest, Direction::SouthEast),
         ];
 
-        Ok(if search_results.iter().any(|result| *result == 4) {
-            Some(*slot)
-        } else {
+        // stop at the first axis with a winning run
+        let won = axes.iter().any(|&(forward, backward)| {
+            let length = self.recurse(slot, row_num, col, 1, forward)
+                + self.recurse(slot, row_num, col, 1, backward)
+                - 1;
+
+            length as usize >= self.win_length
+        });
+
+        Ok(if won { Some(*slot) } else { None })
+    }
+
+    /// Like `is_game_over`, but reports every winning line that passes
+    /// through the given row and column index rather than just the winner.
+    /// There are none for a Blank or Blocked Slot.
+    pub fn is_game_over_detailed(
+        &self,
+        row_num: usize,
+        col: usize,
+        slot: &Slot,
+    ) -> Result<Option<WinInfo>, ClientError> {
+        if let Slot::Blank | Slot::Blocked = slot {
+            return Ok(None);
+        }
+
+        // one step along each of the four axes a line can run
+        let lines = [(1, 0), (0, 1), (-1, 1), (1, 1)]
+            .iter()
+            .map(|&(d_row, d_col)| self.line_through(slot, row_num, col, d_row, d_col))
+            .filter(|line| line.len() >= self.win_length)
+            .collect::<Vec<_>>();
+
+        Ok(if lines.is_empty() {
             None
+        } else {
+            Some(WinInfo { slot: *slot, lines })
+        })
+    }
+
+    /// Scan the whole Board for completed lines, rather than only those
+    /// through a single move. Each winning line is reported once, along
+    /// with the Slot that completed it.
+    pub fn find_all_wins(&self) -> Vec<(Slot, Vec<(usize, usize)>)> {
+        let mut wins = Vec::new();
+
+        for (row_num, row) in self.rows.iter().enumerate() {
+            for (col, slot) in row.0.iter().enumerate() {
+                if let Slot::Blank | Slot::Blocked = slot {
+                    continue;
+                }
+
+                for &(d_row, d_col) in [(1, 0), (0, 1), (-1, 1), (1, 1)].iter() {
+                    let line = self.line_through(slot, row_num, col, d_row, d_col);
+
+                    // only count a run from its first spot, so that every
+                    // spot along it doesn't report it again
+                    if line.len() >= self.win_length && line[0] == (row_num, col) {
+                        wins.push((*slot, line));
+                    }
+                }
+            }
+        }
+
+        wins
+    }
+
+    /// Collects the coordinates of the unbroken run of the given Slot that
+    /// passes through the given row and column index along one axis.
+    fn line_through(
+        &self,
+        slot: &Slot,
+        row_num: usize,
+        col: usize,
+        d_row: isize,
+        d_col: isize,
+    ) -> Vec<(usize, usize)> {
+        let matches = |row_num: isize, col: isize| {
+            row_num >= 0 && col >= 0 && self.get(row_num as usize, col as usize) == Some(slot)
+        };
+
+        // walk back to the start of the run...
+        let (mut r, mut c) = (row_num as isize, col as isize);
+        while matches(r - d_row, c - d_col) {
+            r -= d_row;
+            c -= d_col;
+        }
+
+        // ...then collect it from one end to the other
+        let mut line = Vec::new();
+        while matches(r, c) {
+            line.push((r as usize, c as usize));
+            r += d_row;
+            c += d_col;
+        }
+
+        line
+    }
+
+    /// Computes the winner, if any, that results from playing the given Move
+    /// with the given Slot, without modifying this Board.
+    pub fn winner_after(&self, mov: &Move, slot: Slot) -> Result<Option<Slot>, ClientError> {
+        let mut board = self.clone();
+
+        let (row, col) = match mov.side {
+            Side::Left => board.insert_from_left(mov.row, slot)?,
+            Side::Right => board.insert_from_right(mov.row, slot)?,
+        };
+
+        board.is_game_over(row, col, &slot)
+    }
+
+    /// Every Move that can currently be played: both sides of each Row that
+    /// isn't full.
+    pub fn available_moves(&self) -> Vec<Move> {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !row.is_full())
+            .flat_map(|(row, _)| {
+                [Side::Left, Side::Right]
+                    .iter()
+                    .filter(move |&&side| self.side_allowed(row, side))
+                    .map(move |&side| Move { side, row })
+            })
+            .collect()
+    }
+
+    /// Returns whether the spot at the given coordinates is where the next
+    /// piece inserted into its Row would land, from either Side the Row may
+    /// be played from. Coordinates outside the Board are never reachable.
+    pub fn is_reachable(&self, row_num: usize, col: usize) -> bool {
+        let row = match self.rows.get(row_num) {
+            Some(row) => row,
+            None => return false,
+        };
+
+        let from_left = row.0.iter().rposition(|spot| *spot == Slot::Blank);
+        let from_right = row.0.iter().position(|spot| *spot == Slot::Blank);
+
+        (self.side_allowed(row_num, Side::Left) && from_left == Some(col))
+            || (self.side_allowed(row_num, Side::Right) && from_right == Some(col))
+    }
+
+    /// The first Move, if any, with which the given Player would win the
+    /// game straight away.
+    pub fn winning_move_for(&self, player: Player) -> Option<Move> {
+        let slot = self.slot_for(player);
+        let mut tracker = WinTracker::from_board(self);
+
+        self.available_moves().into_iter().find(|mov| {
+            let mut board = self.clone();
+            let turn = Turn {
+                source: player,
+                mov: *mov,
+            };
+
+            match board.apply_turn(&turn) {
+                Ok((row, col)) => {
+                    let won = tracker.place(row, col, slot);
+                    tracker.undo();
+                    won
+                }
+                Err(_) => false,
+            }
         })
     }
 
@@ -142,126 +848,484 @@ impl Board {
         len_so_far: u32,
         direction: Direction,
     ) -> u32 {
-        // base case
-        if let Slot::Blank = slot {
+        // base case; a run that's already long enough to win needn't be
+        // counted any further
+        if let Slot::Blank | Slot::Blocked = slot {
+            return len_so_far;
+        }
+        if len_so_far as usize >= self.win_length {
             return len_so_far;
         }
 
-        match direction {
-            Direction::North => match self.try_get_row(row_num.overflowing_sub(1).0) {
-                Ok(row) => {
-                    if slot == row.get(col) {
-                        self.recurse(slot, row_num - 1, col, len_so_far + 1, direction)
-                    } else {
-                        len_so_far
-                    }
-                }
-                Err(_) => len_so_far,
-            },
-            Direction::South => match self.try_get_row(row_num + 1) {
-                Ok(row) => {
-                    if slot == row.get(col) {
-                        self.recurse(slot, row_num + 1, col, len_so_far + 1, direction)
-                    } else {
-                        len_so_far
-                    }
-                }
-                Err(_) => len_so_far,
-            },
-            Direction::East => {
-                let row = self.try_get_row(row_num).unwrap();
-
-                if col < self.width - 1 {
-                    if slot == row.get(col + 1) {
-                        return self.recurse(slot, row_num, col + 1, len_so_far + 1, direction);
-                    }
-                }
+        let (d_row, d_col) = direction.offset();
+        let next = row_num
+            .checked_add_signed(d_row)
+            .zip(col.checked_add_signed(d_col));
 
-                len_so_far
+        match next {
+            Some((row_num, col)) if self.get(row_num, col) == Some(slot) => {
+                self.recurse(slot, row_num, col, len_so_far + 1, direction)
             }
-            Direction::West => {
-                let row = self.try_get_row(row_num).unwrap();
+            _ => len_so_far,
+        }
+    }
+}
 
-                if col > 0 {
-                    if slot == row.get(col - 1) {
-                        return self.recurse(slot, row_num, col - 1, len_so_far + 1, direction);
-                    }
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                len_so_far
-            }
-            Direction::NorthEast => match self.try_get_row(row_num.overflowing_sub(1).0) {
-                Ok(row) => {
-                    if col < self.width - 1 {
-                        if slot == row.get(col + 1) {
-                            return self.recurse(
-                                slot,
-                                row_num - 1,
-                                col + 1,
-                                len_so_far + 1,
-                                direction,
-                            );
-                        }
-                    }
+    #[test]
+    fn ascii_art_draws_a_line_between_every_row() {
+        let board = Board::new(3, 5);
+        let art = board.to_ascii_art();
+        let lines = art.lines().collect::<Vec<_>>();
 
-                    len_so_far
-                }
-                Err(_) => len_so_far,
-            },
-            Direction::NorthWest => match self.try_get_row(row_num.overflowing_sub(1).0) {
-                Ok(row) => {
-                    if col > 0 {
-                        if slot == row.get(col - 1) {
-                            return self.recurse(
-                                slot,
-                                row_num - 1,
-                                col - 1,
-                                len_so_far + 1,
-                                direction,
-                            );
-                        }
-                    }
+        // a border above, below, and between each of the Rows
+        assert_eq!(lines.len(), 3 * 2 + 1);
+        assert_eq!(lines.iter().filter(|line| line.contains('─')).count(), 4);
+        assert!(lines[1..lines.len() - 1]
+            .iter()
+            .step_by(2)
+            .all(|line| line.matches('│').count() == 5 + 1));
+    }
 
-                    len_so_far
-                }
-                Err(_) => len_so_far,
-            },
-            Direction::SouthEast => match self.try_get_row(row_num + 1) {
-                Ok(row) => {
-                    if col < self.width - 1 {
-                        if slot == row.get(col + 1) {
-                            return self.recurse(
-                                slot,
-                                row_num + 1,
-                                col + 1,
-                                len_so_far + 1,
-                                direction,
-                            );
-                        }
-                    }
+    #[test]
+    fn svg_draws_every_spot_and_piece() {
+        let mut board = Board::with_blocked(3, 4, &[(1, 1)]);
+        board.insert_from_left(0, Slot::X).unwrap();
+        board.insert_from_right(2, Slot::O).unwrap();
 
-                    len_so_far
-                }
-                Err(_) => len_so_far,
-            },
-            Direction::SouthWest => match self.try_get_row(row_num + 1) {
-                Ok(row) => {
-                    if col > 0 {
-                        if slot == row.get(col - 1) {
-                            return self.recurse(
-                                slot,
-                                row_num + 1,
-                                col - 1,
-                                len_so_far + 1,
-                                direction,
-                            );
-                        }
-                    }
+        let svg = board.to_svg();
 
-                    len_so_far
-                }
-                Err(_) => len_so_far,
-            },
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="160" height="120""#)
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        // the background, then one cell for every spot
+        assert_eq!(svg.matches("<rect").count(), 1 + 3 * 4);
+        assert_eq!(svg.matches(r#"fill="grey""#).count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert_eq!(svg.matches(r#"fill="red""#).count(), 1);
+        assert_eq!(svg.matches(r#"fill="gold""#).count(), 1);
+    }
+
+    #[test]
+    fn equal_boards_hash_equal() {
+        let mut first = Board::new(4, 4);
+        first.insert_from_left(0, Slot::X).unwrap();
+        first.insert_from_right(1, Slot::O).unwrap();
+        // the same pieces, placed in the other order
+        let mut second = Board::new(4, 4);
+        second.insert_from_right(1, Slot::O).unwrap();
+        second.insert_from_left(0, Slot::X).unwrap();
+
+        assert_eq!(first.zobrist_hash(), second.zobrist_hash());
+        assert_eq!(Board::new(4, 4).zobrist_hash(), 0);
+    }
+
+    #[test]
+    fn single_cell_changes_the_hash() {
+        let mut board = Board::new(4, 4);
+        board.insert_from_left(0, Slot::X).unwrap();
+        let before = board.zobrist_hash();
+
+        let mut other_piece = board.clone();
+        other_piece.set_slot(0, 3, Slot::O).unwrap();
+        let mut other_spot = board.clone();
+        other_spot.set_slot(0, 3, Slot::Blank).unwrap();
+        other_spot.set_slot(0, 2, Slot::X).unwrap();
+
+        assert_ne!(other_piece.zobrist_hash(), before);
+        assert_ne!(other_spot.zobrist_hash(), before);
+        // the hash can be updated a spot at a time
+        assert_eq!(
+            before ^ Board::zobrist_key(0, 3, Slot::X) ^ Board::zobrist_key(0, 3, Slot::O),
+            other_piece.zobrist_hash()
+        );
+    }
+
+    #[test]
+    fn reachable_spots_are_the_next_to_fill_from_either_side() {
+        let mut board = Board::decode("____/X___/XOXO").unwrap();
+
+        // an empty Row can be reached at either end
+        assert!(board.is_reachable(0, 0));
+        assert!(board.is_reachable(0, 3));
+        assert!(!board.is_reachable(0, 1));
+        // a half-full Row only where the next piece from each side lands
+        assert!(board.is_reachable(1, 1));
+        assert!(board.is_reachable(1, 3));
+        assert!(!board.is_reachable(1, 0));
+        assert!(!board.is_reachable(1, 2));
+        // a full Row can't be reached at all
+        assert!((0..4).all(|col| !board.is_reachable(2, col)));
+        assert!(!board.is_reachable(3, 0));
+
+        board.side_restrictions.insert(0, Side::Left);
+        assert!(board.is_reachable(0, 3));
+        assert!(!board.is_reachable(0, 0));
+    }
+
+    #[test]
+    fn place_many_builds_a_position() {
+        let mut board = Board::new(3, 3);
+        let left = |row| Move {
+            side: Side::Left,
+            row,
+        };
+        let right = |row| Move {
+            side: Side::Right,
+            row,
+        };
+
+        let placed = board
+            .place_many(&[(left(0), Slot::X), (right(0), Slot::O), (left(2), Slot::X)])
+            .unwrap();
+
+        assert_eq!(placed, vec![(0, 2), (0, 0), (2, 2)]);
+        assert_eq!(board.encode(), "O_X/___/__X");
+    }
+
+    #[test]
+    fn place_many_stops_at_the_first_illegal_move() {
+        let mut board = Board::new(2, 2);
+        let left = Move {
+            side: Side::Left,
+            row: 0,
+        };
+
+        let error = board
+            .place_many(&[(left, Slot::X), (left, Slot::O), (left, Slot::X)])
+            .unwrap_err();
+
+        assert!(matches!(
+            &error,
+            ClientError::IllegalPlacement { index: 3, mov, source }
+                if *mov == left && matches!(**source, ClientError::FullRow)
+        ));
+        assert!(error.to_string().starts_with("Move 3 (0L) of the batch"));
+        // the Moves before it are still on the Board
+        assert_eq!(board.encode(), "OX/__");
+    }
+
+    #[test]
+    fn get_is_none_off_the_board() {
+        let board = Board::decode("X__/_O_").unwrap();
+
+        assert_eq!(board.get(0, 0), Some(&Slot::X));
+        assert_eq!(board.get(1, 1), Some(&Slot::O));
+        assert_eq!(board.get(1, 2), Some(&Slot::Blank));
+        assert_eq!(board.get(2, 0), None);
+        assert_eq!(board.get(0, 3), None);
+        assert_eq!(board.get(usize::MAX, usize::MAX), None);
+    }
+
+    #[test]
+    fn blank_and_blocked_slots_never_win() {
+        let board = Board::with_blocked(4, 4, &[(0, 0)]);
+
+        assert_eq!(board.is_game_over(0, 0, &Slot::Blocked).unwrap(), None);
+        assert_eq!(board.is_game_over(1, 1, &Slot::Blank).unwrap(), None);
+        assert!(board
+            .is_game_over_detailed(1, 1, &Slot::Blank)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn blocked_slots_are_never_filled() {
+        let mut board = Board::with_blocked(4, 4, &[(0, 1), (9, 9)]);
+
+        while board.insert_from_left(0, Slot::X).is_ok() {}
+        while board.insert_from_right(0, Slot::O).is_ok() {}
+
+        assert_eq!(board.get(0, 1), Some(&Slot::Blocked));
+        assert_eq!(board.count_pieces(), 3);
+        assert_eq!(board.capacity(), 15);
+    }
+
+    #[test]
+    fn rows_iter_yields_every_row_from_the_top() {
+        let mut board = Board::new(3, 5);
+        board.insert_from_left(0, Slot::X).unwrap();
+
+        let rows = board.rows_iter().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.0.len() == 5));
+        assert_eq!(rows[0].0[4], Slot::X);
+    }
+
+    #[test]
+    fn winning_move_is_found_only_when_there_is_one() {
+        let mut board = Board::new(4, 4);
+        assert_eq!(board.winning_move_for(Player::First), None);
+
+        // three of the first Player's pieces down the right edge, and three
+        // of the second Player's down the left edge
+        let (first, second) = (
+            board.slot_for(Player::First),
+            board.slot_for(Player::Second),
+        );
+        for row in 0..3 {
+            board.insert_from_left(row, first).unwrap();
+            board.insert_from_right(row, second).unwrap();
+        }
+
+        // only the last Row completes the column, from the first Player's side
+        assert_eq!(
+            board.winning_move_for(Player::First),
+            Some(Move {
+                side: Side::Left,
+                row: 3,
+            })
+        );
+
+        // once it's blocked, neither Player has a winning move left
+        board.insert_from_left(3, second).unwrap();
+        board.insert_from_right(3, first).unwrap();
+        assert_eq!(board.winning_move_for(Player::First), None);
+        assert_eq!(board.winning_move_for(Player::Second), None);
+    }
+
+    #[test]
+    fn restricted_row_is_only_played_from_its_side() {
+        let mut board = Board::new(3, 3);
+        board.side_restrictions.insert(1, Side::Right);
+
+        assert!(matches!(
+            board.insert_from_left(1, Slot::X),
+            Err(ClientError::BlockedSide {
+                row: 1,
+                allowed: Side::Right
+            })
+        ));
+        assert_eq!(board.count_pieces(), 0);
+
+        assert_eq!(board.insert_from_right(1, Slot::X).unwrap(), (1, 0));
+        // the other Rows can still be played from either side
+        assert!(board.insert_from_left(0, Slot::O).is_ok());
+        assert!(board.insert_from_right(0, Slot::X).is_ok());
+    }
+
+    #[test]
+    fn last_move_marker_follows_the_newest_piece() {
+        let mut board = Board::new(3, 3);
+
+        let first = board.insert_from_left(0, Slot::X).unwrap();
+        assert_eq!(board.last_move, Some(first));
+        let second = board.insert_from_right(1, Slot::O).unwrap();
+        assert_eq!(second, (1, 0));
+        assert_eq!(board.last_move, Some(second));
+
+        let rendered = board.render_with_last_move();
+        assert_eq!(rendered.matches('*').count(), 1);
+        assert!(rendered
+            .lines()
+            .nth(1)
+            .unwrap()
+            .contains(&format!("{}*", Slot::O)));
+    }
+
+    #[test]
+    fn only_a_piece_can_be_undone() {
+        let mut board = Board::with_blocked(3, 3, &[(1, 1)]);
+        let (row, col) = board.insert_from_left(0, Slot::X).unwrap();
+
+        assert!(matches!(
+            board.undo_move(0, 3),
+            Err(ClientError::NonexistentColumn)
+        ));
+        assert!(matches!(
+            board.undo_move(3, 0),
+            Err(ClientError::NonexistentRow)
+        ));
+        assert!(matches!(
+            board.undo_move(1, 1),
+            Err(ClientError::NoPieceToUndo)
+        ));
+        assert!(matches!(
+            board.undo_move(2, 0),
+            Err(ClientError::NoPieceToUndo)
+        ));
+        assert_eq!(board.get(1, 1), Some(&Slot::Blocked));
+
+        assert_eq!(board.undo_move(row, col).unwrap(), Slot::X);
+        assert_eq!(board.count_pieces(), 0);
+    }
+
+    #[test]
+    fn blocked_slot_breaks_a_run() {
+        let mut board = Board::with_blocked(4, 7, &[(0, 3)]);
+        for &col in &[0, 1, 2, 4, 5, 6] {
+            board.set_slot(0, col, Slot::O).unwrap();
+        }
+
+        assert_eq!(board.is_game_over(0, 2, &Slot::O).unwrap(), None);
+        assert_eq!(board.is_game_over(0, 4, &Slot::O).unwrap(), None);
+    }
+
+    #[test]
+    fn winner_after_reports_a_winning_move_without_playing_it() {
+        let mut board = Board::new(7, 7);
+        for _ in 0..3 {
+            board.insert_from_left(0, Slot::X).unwrap();
+        }
+        let before = board.encode();
+
+        let winning = Move {
+            side: Side::Left,
+            row: 0,
+        };
+        let elsewhere = Move {
+            side: Side::Left,
+            row: 1,
+        };
+
+        assert_eq!(
+            board.winner_after(&winning, Slot::X).unwrap(),
+            Some(Slot::X)
+        );
+        assert_eq!(board.winner_after(&winning, Slot::O).unwrap(), None);
+        assert_eq!(board.winner_after(&elsewhere, Slot::X).unwrap(), None);
+        assert_eq!(board.encode(), before);
+    }
+
+    #[test]
+    fn detailed_win_lists_every_line_the_move_completes() {
+        let mut board = Board::new(7, 7);
+        for &(row, col) in &[(3, 0), (3, 1), (3, 2), (0, 0), (1, 1), (2, 2), (3, 3)] {
+            board.set_slot(row, col, Slot::X).unwrap();
         }
+
+        let info = board
+            .is_game_over_detailed(3, 3, &Slot::X)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(info.slot, Slot::X);
+        assert_eq!(
+            info.lines,
+            vec![
+                vec![(3, 0), (3, 1), (3, 2), (3, 3)],
+                vec![(0, 0), (1, 1), (2, 2), (3, 3)],
+            ]
+        );
+        assert!(board
+            .is_game_over_detailed(0, 0, &Slot::X)
+            .unwrap()
+            .is_some());
+        assert!(board
+            .is_game_over_detailed(3, 3, &Slot::O)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn column_reads_top_to_bottom() {
+        let board = Board::decode("X__/O#_/_XO").unwrap();
+
+        assert_eq!(
+            board.column(0).unwrap(),
+            vec![&Slot::X, &Slot::O, &Slot::Blank]
+        );
+        assert_eq!(
+            board.column(1).unwrap(),
+            vec![&Slot::Blank, &Slot::Blocked, &Slot::X]
+        );
+        assert!(matches!(
+            board.column(3),
+            Err(ClientError::NonexistentColumn)
+        ));
+    }
+
+    #[test]
+    fn square_board_has_expected_diagonals() {
+        let diagonals = Board::new(4, 4).diagonals();
+        let lengths = diagonals.iter().map(Vec::len).collect::<Vec<_>>();
+
+        // the `\` diagonals, then the `/` ones
+        assert_eq!(lengths, vec![1, 2, 3, 4, 3, 2, 1, 1, 2, 3, 4, 3, 2, 1]);
+        assert_eq!(diagonals[3], vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+        assert_eq!(diagonals[10], vec![(0, 3), (1, 2), (2, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn mirroring_twice_restores_the_board() {
+        let board = Board::decode("X__O/_O#_/XXO_").unwrap();
+
+        assert_eq!(board.mirror_horizontal().encode(), "O__X/_#O_/_OXX");
+        assert_eq!(
+            board.mirror_horizontal().mirror_horizontal().encode(),
+            board.encode()
+        );
+    }
+
+    #[test]
+    fn mirroring_swaps_side_restrictions() {
+        let mut board = Board::new(3, 3);
+        board.side_restrictions.insert(0, Side::Left);
+        board.side_restrictions.insert(2, Side::Right);
+        let mirrored = board.mirror_horizontal();
+
+        assert_eq!(mirrored.side_restrictions[&0], Side::Right);
+        assert_eq!(mirrored.side_restrictions[&2], Side::Left);
+        assert!(!mirrored.side_restrictions.contains_key(&1));
+    }
+
+    #[test]
+    fn opposite_side_is_across_the_row() {
+        assert_eq!(Side::Left.opposite(), Side::Right);
+        assert_eq!(Side::Right.opposite(), Side::Left);
+        for side in [Side::Left, Side::Right].iter() {
+            assert_eq!(side.opposite().opposite(), *side);
+        }
+    }
+
+    #[test]
+    fn mirrored_win_is_still_a_win() {
+        let mut board = Board::new(5, 5);
+        for row in 0..4 {
+            board.set_slot(row, 4 - row, Slot::O).unwrap();
+        }
+        let mirrored = board.mirror_horizontal();
+
+        assert_eq!(board.is_game_over(0, 4, &Slot::O).unwrap(), Some(Slot::O));
+        assert_eq!(
+            mirrored.is_game_over(0, 0, &Slot::O).unwrap(),
+            Some(Slot::O)
+        );
+        assert_eq!(mirrored.last_move, Some((3, 3)));
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let board = Board::decode("XO_/__#").unwrap();
+        let transposed = board.transpose();
+
+        assert_eq!(transposed.encode(), "X_/O_/_#");
+        assert_eq!((transposed.height, transposed.width), (3, 2));
+        assert_eq!(transposed.transpose().encode(), board.encode());
+    }
+
+    #[test]
+    fn every_separate_win_is_found_once() {
+        let mut board = Board::new(7, 7);
+        // a run longer than needed still counts as one win
+        for col in 0..5 {
+            board.set_slot(0, col, Slot::X).unwrap();
+        }
+        for row in 2..6 {
+            board.set_slot(row, 6, Slot::O).unwrap();
+        }
+
+        assert_eq!(
+            board.find_all_wins(),
+            vec![
+                (Slot::X, vec![(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)]),
+                (Slot::O, vec![(2, 6), (3, 6), (4, 6), (5, 6)]),
+            ]
+        );
+        assert!(Board::new(7, 7).find_all_wins().is_empty());
     }
 }
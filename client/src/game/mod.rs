@@ -1,6 +1,11 @@
+use std::env;
 use std::fmt;
 
+use crate::{error::ClientError, Turn};
+use board::Board;
+
 pub mod board;
+pub mod tracker;
 
 /// The possible variants of a single slot in a Board.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -11,6 +16,8 @@ pub enum Slot {
     X,
     /// A slot owned by the player playing O.
     O,
+    /// A permanently unplayable slot owned by neither player.
+    Blocked,
 }
 
 impl fmt::Display for Slot {
@@ -19,12 +26,46 @@ impl fmt::Display for Slot {
             Slot::Blank => write!(f, "_"),
             Slot::X => write!(f, "X"),
             Slot::O => write!(f, "O"),
+            Slot::Blocked => write!(f, "#"),
+        }
+    }
+}
+
+/// How Boards are drawn on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoardStyle {
+    /// Plain ASCII, readable on any terminal.
+    Plain,
+    /// A grid drawn with box-drawing characters.
+    BoxDrawing,
+}
+
+impl BoardStyle {
+    /// Pick the style for this terminal: plain when asked for with
+    /// `no_unicode`, when `NO_COLOR` is set, or when the locale isn't
+    /// UTF-8, and box-drawing otherwise.
+    pub fn detect(no_unicode: bool) -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+        // the first of these that is set decides the character encoding
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+
+        if no_unicode || no_color || !utf8 {
+            BoardStyle::Plain
+        } else {
+            BoardStyle::BoxDrawing
         }
     }
 }
 
 /// The directions in which a 4-length sequence of Slots constitutes a win.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Direction {
     North,
     NorthWest,
@@ -36,8 +77,34 @@ pub enum Direction {
     West,
 }
 
+impl Direction {
+    /// The change in row and column from one Slot to the next in this
+    /// Direction.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::NorthWest => (-1, -1),
+            Direction::NorthEast => (-1, 1),
+            Direction::South => (1, 0),
+            Direction::SouthWest => (1, -1),
+            Direction::SouthEast => (1, 1),
+            Direction::East => (0, 1),
+            Direction::West => (0, -1),
+        }
+    }
+}
+
+/// Every winning line completed by a single move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WinInfo {
+    /// The Slot that won.
+    pub slot: Slot,
+    /// The coordinates of each winning line, ordered from one end to the other.
+    pub lines: Vec<Vec<(usize, usize)>>,
+}
+
 /// A Row of the Board.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Row(Vec<Slot>);
 
 impl fmt::Display for Row {
@@ -45,7 +112,7 @@ impl fmt::Display for Row {
         write!(f, "[ ")?;
 
         for slot in self.0.iter() {
-            write!(f, "{} ", slot.to_string())?;
+            write!(f, "{} ", slot)?;
         }
 
         write!(f, "]")
@@ -58,13 +125,46 @@ impl Row {
         self.0.len()
     }
 
-    /// Returns whether the Row contains no Blank Slots.
+    /// Returns whether the Row has no Slots at all.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns whether the Row contains no Blank Slots. Blocked Slots are
+    /// never filled, so they count towards a full Row.
     pub fn is_full(&self) -> bool {
         self.0.iter().all(|slot| *slot != Slot::Blank)
     }
 
-    /// Get the Slot at the given column index in the Row.
-    pub fn get(&self, col: usize) -> &Slot {
-        &self.0[col]
+    /// Get the Slot at the given column index in the Row, or `None` if the
+    /// Row isn't that long.
+    pub fn get(&self, col: usize) -> Option<&Slot> {
+        self.0.get(col)
+    }
+}
+
+/// Check that a recorded game could actually have been played on a Board
+/// of the specified height and width: every Turn must land on the Board,
+/// and the Players must strictly alternate, starting with whoever took the
+/// first Turn.
+pub fn validate_turns(height: usize, width: usize, turns: &[Turn]) -> Result<(), ClientError> {
+    let mut board = Board::new(height, width);
+    let mut expected = turns.first().map(|turn| turn.source);
+
+    for (index, turn) in turns.iter().enumerate() {
+        let illegal = |source| ClientError::IllegalTurn {
+            turn: index + 1,
+            source: Box::new(source),
+        };
+
+        if expected != Some(turn.source) {
+            return Err(illegal(ClientError::OutOfTurn));
+        }
+
+        board.apply_turn(turn).map_err(illegal)?;
+
+        expected = Some(!turn.source);
     }
+
+    Ok(())
 }
@@ -0,0 +1,132 @@
+use super::{board::Board, Slot};
+
+/// One step along each of the four axes a line can run.
+const AXES: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 1), (1, 1)];
+
+/// A piece placed on a WinTracker, along with the run lengths it
+/// overwrote as `(spot, axis, length)` so that it can be taken back.
+#[derive(Debug, Clone)]
+struct Placement {
+    spot: usize,
+    overwritten: Vec<(usize, usize, usize)>,
+}
+
+/// Keeps track of the runs of pieces along each axis as pieces are placed,
+/// so that whether a piece completes a line is known without scanning the
+/// Board from it. Pieces must be taken back in the reverse of the order
+/// they were placed in.
+#[derive(Debug, Clone)]
+pub struct WinTracker {
+    width: usize,
+    height: usize,
+    win_length: usize,
+    /// The Slot in each spot, by `row * width + col`.
+    slots: Vec<Slot>,
+    /// The length of the run along each axis that each spot is at the end
+    /// of. Only the two ends of a run are kept up to date.
+    runs: Vec<[usize; 4]>,
+    /// Each piece placed so far, in order.
+    placed: Vec<Placement>,
+}
+
+impl WinTracker {
+    /// Initialize a WinTracker for an empty Board of the given dimensions.
+    pub fn new(height: usize, width: usize, win_length: usize) -> Self {
+        WinTracker {
+            width,
+            height,
+            win_length,
+            slots: vec![Slot::Blank; height * width],
+            runs: vec![[0; 4]; height * width],
+            placed: Vec::new(),
+        }
+    }
+
+    /// Initialize a WinTracker with the pieces already on the given Board.
+    pub fn from_board(board: &Board) -> Self {
+        let mut tracker = Self::new(board.height, board.width, board.win_length);
+
+        for (row_num, row) in board.rows_iter().enumerate() {
+            for (col, slot) in row.0.iter().enumerate() {
+                if let Slot::X | Slot::O = slot {
+                    tracker.place(row_num, col, *slot);
+                }
+            }
+        }
+
+        tracker
+    }
+
+    /// The index of the spot one step from the given coordinates in the
+    /// given direction, if it's on the Board.
+    fn step(&self, row_num: usize, col: usize, d_row: isize, d_col: isize) -> Option<usize> {
+        let row_num = row_num as isize + d_row;
+        let col = col as isize + d_col;
+
+        if row_num < 0 || col < 0 || row_num as usize >= self.height || col as usize >= self.width {
+            return None;
+        }
+
+        Some(row_num as usize * self.width + col as usize)
+    }
+
+    /// The length of the run of `slot` one step from the given coordinates
+    /// in the given direction along the given axis.
+    fn run_beside(
+        &self,
+        row_num: usize,
+        col: usize,
+        axis: usize,
+        (d_row, d_col): (isize, isize),
+        slot: Slot,
+    ) -> usize {
+        match self.step(row_num, col, d_row, d_col) {
+            Some(spot) if self.slots[spot] == slot => self.runs[spot][axis],
+            _ => 0,
+        }
+    }
+
+    /// Record a piece placed at the given coordinates, joining it to the
+    /// runs of the same Slot beside it. Returns whether it completes a line
+    /// of `win_length`.
+    pub fn place(&mut self, row_num: usize, col: usize, slot: Slot) -> bool {
+        let spot = row_num * self.width + col;
+        let mut overwritten = Vec::with_capacity(8);
+        let mut won = false;
+
+        for (axis, &(d_row, d_col)) in AXES.iter().enumerate() {
+            let backward = self.run_beside(row_num, col, axis, (-d_row, -d_col), slot);
+            let forward = self.run_beside(row_num, col, axis, (d_row, d_col), slot);
+            let length = backward + forward + 1;
+            won |= length >= self.win_length;
+
+            // the joined run's new ends
+            let stride = d_row * self.width as isize + d_col;
+            let start = spot as isize - backward as isize * stride;
+            let end = spot as isize + forward as isize * stride;
+
+            for end in [start as usize, end as usize].iter() {
+                overwritten.push((*end, axis, self.runs[*end][axis]));
+                self.runs[*end][axis] = length;
+            }
+        }
+
+        self.slots[spot] = slot;
+        self.placed.push(Placement { spot, overwritten });
+
+        won
+    }
+
+    /// Take back the most recently placed piece. Returns its coordinates,
+    /// or `None` if nothing has been placed.
+    pub fn undo(&mut self) -> Option<(usize, usize)> {
+        let Placement { spot, overwritten } = self.placed.pop()?;
+
+        for (end, axis, length) in overwritten.into_iter().rev() {
+            self.runs[end][axis] = length;
+        }
+        self.slots[spot] = Slot::Blank;
+
+        Some((spot / self.width, spot % self.width))
+    }
+}
@@ -0,0 +1,65 @@
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::error::ClientError;
+
+/// How many of the most recently used server addresses are remembered.
+const HISTORY_LEN: usize = 10;
+
+/// Where the history of server addresses is kept: a file under
+/// `$XDG_CONFIG_HOME`, or under `~/.config` if that isn't set.
+pub fn history_path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_dir.join("sidestacker").join("history"))
+}
+
+/// The server addresses connected to recently, most recent first. A
+/// missing or unreadable history is treated as empty.
+pub fn recent() -> Vec<SocketAddr> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|history| {
+            history
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The server address connected to most recently, if any.
+pub fn last() -> Option<SocketAddr> {
+    recent().into_iter().next()
+}
+
+/// Remember that the client connected to the given server address, moving
+/// it to the front of the history if it's already there.
+pub fn record(addr: SocketAddr) -> Result<(), ClientError> {
+    let path = match history_path() {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    let mut addrs = recent();
+    addrs.retain(|recent| *recent != addr);
+    addrs.insert(0, addr);
+    addrs.truncate(HISTORY_LEN);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let history = addrs
+        .iter()
+        .map(|addr| format!("{}\n", addr))
+        .collect::<String>();
+    fs::write(path, history)?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::io::{self, prelude::*};
+
+use crate::error::ClientError;
+
+/// Where a Player's input at the move prompt comes from.
+pub trait MoveSource {
+    /// Read the next line of input, or `None` once there is no more.
+    fn next_line(&mut self) -> Result<Option<String>, ClientError>;
+}
+
+/// Reads input typed by the Player.
+#[derive(Debug, Default)]
+pub struct Stdin;
+
+impl MoveSource for Stdin {
+    fn next_line(&mut self) -> Result<Option<String>, ClientError> {
+        io::stdout()
+            .flush()
+            .map_err(|e| ClientError::InputError { source: e })?;
+
+        let mut input = String::new();
+        let read = io::stdin()
+            .read_line(&mut input)
+            .map_err(|e| ClientError::InputError { source: e })?;
+
+        Ok(if read == 0 { None } else { Some(input) })
+    }
+}
+
+/// Plays back a fixed list of lines, in order, as if they were typed.
+#[derive(Debug, Default)]
+pub struct Scripted(pub Vec<String>);
+
+impl MoveSource for Scripted {
+    fn next_line(&mut self) -> Result<Option<String>, ClientError> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+
+        let line = self.0.remove(0);
+        println!("{}", line);
+
+        Ok(Some(line))
+    }
+}
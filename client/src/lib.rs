@@ -1,19 +1,60 @@
-use futures::StreamExt;
+use futures::{sink::SinkExt, StreamExt};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
+use std::io;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
-use tokio::net::TcpStream;
-use tokio_util::codec::{Framed, LinesCodec};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UnixStream};
+use tokio_util::codec::Framed;
 
 use error::ClientError;
+use game::Slot;
+use input::MoveSource;
+use rules::Lang;
 use session::Session;
+use transcript::{Transcript, TranscriptCodec};
 
+pub mod ai;
 pub mod error;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fixtures;
 pub mod game;
+pub mod history;
+pub mod input;
+pub mod replay;
+pub mod rules;
 pub mod session;
+pub mod transcript;
+
+/// The major version of the protocol spoken between client and server. Bump
+/// this whenever a Request or Response changes in a way older builds can't
+/// understand, and reset the minor version.
+pub const PROTOCOL_VERSION: u32 = 21;
+
+/// The minor version of the protocol. Bump this for changes older builds
+/// can safely ignore, such as a new optional field. Clients and servers
+/// with different minor versions still play together.
+pub const PROTOCOL_MINOR_VERSION: u32 = 1;
+
+/// Fail with a `ProtocolMismatch` unless the server speaks the same major
+/// version of the protocol as this client. Only the major version matters,
+/// since minor versions differ only in ways either side can ignore.
+pub fn check_protocol_version(server: u32) -> Result<(), ClientError> {
+    if server != PROTOCOL_VERSION {
+        return Err(ClientError::ProtocolMismatch {
+            server,
+            client: PROTOCOL_VERSION,
+        });
+    }
+
+    Ok(())
+}
 
 #[derive(StructOpt, Debug)]
 #[structopt(
@@ -23,6 +64,8 @@ pub mod session;
 pub enum Client {
     /// Connect to a SideStacker Session
     Connect(Params),
+    /// Step through a recorded game
+    Replay(ReplayParams),
 }
 
 #[derive(StructOpt, Debug)]
@@ -30,10 +73,85 @@ pub enum Client {
 pub struct Params {
     #[structopt(short, long, default_value = "0.0.0.0:8080")]
     pub addr: SocketAddr,
+    /// Connect to a server listening on a Unix domain socket at this path
+    /// instead of on `addr`.
+    #[structopt(long, parse(from_os_str))]
+    pub unix_socket: Option<PathBuf>,
+    /// Connect to the server connected to most recently instead of on
+    /// `addr`.
+    #[structopt(long)]
+    pub last: bool,
+    /// How many times to retry connecting if the server isn't reachable.
+    #[structopt(long, default_value = "0")]
+    pub connect_retries: u32,
+    /// How long to wait between connection attempts, in milliseconds.
+    #[structopt(long, default_value = "500")]
+    pub retry_delay_ms: u64,
+    /// Play against the computer locally instead of connecting to a server.
+    #[structopt(long)]
+    pub offline: bool,
+    /// Start the game against the computer from the encoded Board in this
+    /// file, e.g. `X__/O__/___`, instead of an empty one. Only allowed
+    /// with `--offline`.
+    #[structopt(long, parse(from_os_str), requires = "offline")]
+    pub board_from_file: Option<PathBuf>,
+    /// How many times to try reconnecting and rejoining the game if the
+    /// connection fails partway through it.
+    #[structopt(long, default_value = "0")]
+    pub reconnect_attempts: u32,
+    /// The language to explain the rules in: `en`, `es`, or `fr`.
+    #[structopt(long, default_value = "en")]
+    pub lang: Lang,
+    /// Show the rules from this file instead of the bundled ones.
+    #[structopt(long, parse(from_os_str))]
+    pub rules_text: Option<PathBuf>,
+    /// Play both sides of the game, if the server allows it.
+    #[structopt(long)]
+    pub self_play: bool,
+    /// Show the position each Move leads to and ask before playing it.
+    #[structopt(long)]
+    pub confirm_moves: bool,
+    /// Let the computer choose this client's Moves, for testing servers
+    /// under load. Any question the game asks is answered no.
+    #[structopt(long)]
+    pub auto_move: bool,
+    /// Append every line sent to or received from the server to this file,
+    /// for debugging.
+    #[structopt(long, parse(from_os_str))]
+    pub transcript: Option<PathBuf>,
+    /// Draw the board in plain ASCII, even if the terminal supports UTF-8.
+    /// Setting `NO_COLOR` does the same.
+    #[structopt(long)]
+    pub no_unicode: bool,
+    /// Print each Move played to stderr as it would be typed, prefixed
+    /// with the Player who played it, e.g. `First 3R`.
+    #[structopt(long)]
+    pub echo_moves: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Replay parameters")]
+pub struct ReplayParams {
+    /// A JSON file containing the game's list of Turns.
+    #[structopt(parse(from_os_str))]
+    pub path: PathBuf,
+    /// The height of the game board.
+    #[structopt(short, long, default_value = "7")]
+    pub height: usize,
+    /// The width of the game board.
+    #[structopt(short, long, default_value = "7")]
+    pub width: usize,
+    /// How long to show each move during continuous playback, in milliseconds.
+    #[structopt(long, default_value = "1000")]
+    pub replay_speed: u64,
+    /// Draw the board in plain ASCII, even if the terminal supports UTF-8.
+    /// Setting `NO_COLOR` does the same.
+    #[structopt(long)]
+    pub no_unicode: bool,
 }
 
 /// The Player variants.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Player {
     /// First Player
     First,
@@ -41,6 +159,37 @@ pub enum Player {
     Second,
 }
 
+impl Player {
+    /// Where this Player's entry goes in an array holding something for
+    /// each Player: 0 for the first Player and 1 for the second.
+    pub fn index(self) -> usize {
+        match self {
+            Player::First => 0,
+            Player::Second => 1,
+        }
+    }
+
+    /// The Player whose entry is at the given index of an array holding
+    /// something for each Player, if any.
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Player::First),
+            1 => Some(Player::Second),
+            _ => None,
+        }
+    }
+
+    /// The Slot this Player's pieces are shown as: `X` for the first Player
+    /// and `O` for the second, or the other way around if the symbols are
+    /// swapped.
+    pub fn symbol(self, swap_symbols: bool) -> Slot {
+        match (self, swap_symbols) {
+            (Player::First, false) | (Player::Second, true) => Slot::X,
+            (Player::Second, false) | (Player::First, true) => Slot::O,
+        }
+    }
+}
+
 impl std::ops::Not for Player {
     type Output = Self;
 
@@ -61,13 +210,87 @@ impl fmt::Display for Player {
     }
 }
 
+/// Every rule that both sides of a game need to agree on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ruleset {
+    /// The height of the game board.
+    pub height: usize,
+    /// The width of the game board.
+    pub width: usize,
+    /// How many consecutive pieces a Player needs to win.
+    pub win_length: usize,
+    /// The Player who moves first.
+    pub first_player: Player,
+    /// Whether pieces fall to the bottom of their column once inserted.
+    pub gravity: bool,
+    /// Whether the first Player plays `O` and the second plays `X`.
+    #[serde(default)]
+    pub swap_symbols: bool,
+    /// How long each Player has to take their Turn before the game is
+    /// closed, in seconds. There's no limit if this isn't set.
+    #[serde(default)]
+    pub turn_timeout_secs: Option<u64>,
+    /// The only Side each of these Rows may be played from. Rows that
+    /// aren't listed may be played from either Side.
+    #[serde(default)]
+    pub side_restrictions: HashMap<usize, Side>,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            height: 7,
+            width: 7,
+            win_length: 4,
+            first_player: Player::First,
+            gravity: false,
+            swap_symbols: false,
+            turn_timeout_secs: None,
+            side_restrictions: HashMap::new(),
+        }
+    }
+}
+
+/// How a game ended, from the point of view of one Player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameResult {
+    /// The given Player won.
+    Win(Player),
+    /// The Board filled up without anyone winning.
+    Tie,
+    /// The other Player left before the game was over.
+    OpponentLeft,
+    /// This Player quit before the game was over.
+    LocalQuit,
+    /// The given Player resigned before the game was over.
+    Resigned(Player),
+}
+
 /// The sides from which Players may choose to insert a slot.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Side {
     Left,
     Right,
 }
 
+impl Side {
+    /// The name of this Side, as written out in a sentence.
+    pub fn name(self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        }
+    }
+
+    /// The Side across the Row from this one.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
 impl fmt::Display for Side {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -78,7 +301,7 @@ impl fmt::Display for Side {
 }
 
 /// Represents a Player's move.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Move {
     pub side: Side,
     pub row: usize,
@@ -88,18 +311,20 @@ impl TryFrom<String> for Move {
     type Error = ClientError;
 
     fn try_from(command: String) -> Result<Self, Self::Error> {
-        let chars = command.trim().chars().collect::<Vec<_>>();
+        let command = command.trim();
 
-        if chars.len() != 2 {
-            return Err(ClientError::InvalidMoveFormat);
-        }
+        // the side is always the last character; everything before it is the row
+        let (row, side) = match command.char_indices().last() {
+            Some((index, side)) if index > 0 => (&command[..index], side),
+            _ => return Err(ClientError::InvalidMoveFormat),
+        };
 
-        let row = match chars[0].to_digit(10) {
-            Some(num) => num as usize,
-            None => return Err(ClientError::NonexistentRow),
+        let row = match row.parse::<usize>() {
+            Ok(num) => num,
+            Err(_) => return Err(ClientError::NonexistentRow),
         };
 
-        let side = match chars[1] {
+        let side = match side {
             'l' | 'L' => Side::Left,
             'r' | 'R' => Side::Right,
             _ => return Err(ClientError::InvalidSide),
@@ -109,6 +334,15 @@ impl TryFrom<String> for Move {
     }
 }
 
+impl Move {
+    /// Write the Move as a command in the same `[ROW-NUMBER][SIDE]` format
+    /// that players type, e.g. `3R`. Unlike `Display`, the result can be
+    /// parsed back into a Move.
+    pub fn to_command(&self) -> String {
+        format!("{}{}", self.row, self.side)
+    }
+}
+
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({}{})", self.row, self.side)
@@ -116,74 +350,381 @@ impl fmt::Display for Move {
 }
 
 /// A Player's turn.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Turn {
-    source: Player,
-    mov: Move,
+    pub source: Player,
+    pub mov: Move,
 }
 
-/// Requests the server receives from clients.
-#[derive(Debug, Serialize, Deserialize)]
+/// Requests the server receives from clients. New variants may be added
+/// as the protocol grows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Request {
     /// A client requests to join the game.
     Join,
+    /// A client requests to reclaim the seat it held before disconnecting,
+    /// with the token it was given in its `Welcome`.
+    Rejoin { player: Player, token: String },
+    /// A client requests to watch the game without playing.
+    Spectate,
+    /// A client requests the status of the game without joining it.
+    Status,
     /// A client submits a `Turn` action.
     Turn(Turn),
+    /// A Player asks to take back the last exchange of Turns.
+    Undo,
+    /// A Player answers the other Player's request to undo.
+    UndoReply { accept: bool },
+    /// A Player concedes the game to the other Player.
+    Resign,
+    /// A client requests to hold both seats and play both sides. Only
+    /// servers started with `--allow-self-play` accept this.
+    SelfPlay,
+    /// A client asks for the rendered board with every Turn, for thin
+    /// clients that don't keep track of the board themselves. Sent as the
+    /// first message, it also joins the game like `Join`.
+    ThinClient,
+}
+
+/// The kinds of error the server reports, so that clients can tell them
+/// apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// The Turn can't be played on the Board.
+    InvalidMove,
+    /// A Player attempted to act out of turn, or on behalf of the other
+    /// Player.
+    NotYourTurn,
+    /// The client is sending messages too quickly.
+    RateLimited,
+    /// The server doesn't allow what the client asked for.
+    Forbidden,
+    /// Something went wrong on the server.
+    Internal,
+    /// The server already has as many connections as it allows.
+    ServerFull,
 }
 
-/// The server's responses to client requests.
-#[derive(Debug, Serialize, Deserialize)]
+/// The server's responses to client requests. New variants may be added
+/// as the protocol grows, and clients skip any they don't recognize.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Response {
     /// There is enough capacity in the game. Tell the client which
     /// Player they are.
     Welcome {
         player: Player,
-        height: usize,
-        width: usize,
+        ruleset: Ruleset,
+        /// Missing from servers that predate versioning, which then count
+        /// as version 0.
+        #[serde(default)]
+        protocol_version: u32,
+        /// Missing from servers that predate minor versions, which then
+        /// count as minor version 0.
+        #[serde(default)]
+        protocol_minor_version: u32,
+        /// The name the server was given to tell it apart from others.
+        #[serde(default)]
+        server_name: Option<String>,
+        /// The encoded Board the game started from, if it didn't start
+        /// from an empty one.
+        #[serde(default)]
+        start_position: Option<String>,
+        /// The secret the Player presents to reclaim their seat after
+        /// disconnecting, if the server holds seats open for them.
+        #[serde(default)]
+        reconnect_token: Option<String>,
+        /// How many games the match being played lasts at most, if the
+        /// Players are playing a series of games rather than a single one.
+        #[serde(default)]
+        best_of: Option<u32>,
     },
     /// There are enough Players for the game to start.
     GameStart,
     /// There is not enough capacity in the game.
     GameFull,
-    /// A Player attempted to act out of turn.
-    NotYourTurn,
+    /// A client asked to play after the game had already started. It can
+    /// still be watched by spectating.
+    GameInProgress,
+    /// There is no room for any more spectators.
+    SpectatorFull,
+    /// The game was closed after going idle for too long.
+    IdleTimeout,
     /// Server sends the current Player's Turn to the other Player.
     Turn(Turn),
-    /// Server acknowledges a Player's proposed Turn.
-    Acknowledged,
+    /// Server sends the current Player's Turn to the other Player, along
+    /// with who moves next, when that isn't simply the other Player.
+    Played { turn: Turn, next_player: Player },
+    /// Server acknowledges a Player's proposed Turn, along with where it
+    /// landed and what it means for the game. `game_over` is `None` while
+    /// the game continues, `Some(None)` for a tie, and `Some(Some(player))`
+    /// for a win. `board` is the rendered board after the Turn, sent only
+    /// to thin clients.
+    Acknowledged {
+        coordinate: (usize, usize),
+        game_over: Option<Option<Player>>,
+        next_player: Player,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        board: Option<String>,
+    },
     /// The other Player disconnected.
     PlayerDisconnected,
-    /// An internal server error occurred.
-    ServerError,
+    /// A snapshot of the game for a client that starts watching it midway
+    /// or rejoins it, with the board encoded as its rows separated by `/`.
+    /// `display` is the board rendered for showing as is, for viewers that
+    /// don't decode it themselves. `turns` is the game's history of Turns,
+    /// for a rejoining Player who wants to take some of them back.
+    BoardState {
+        board: String,
+        #[serde(default)]
+        display: String,
+        current_player: Player,
+        #[serde(default)]
+        turns: Vec<Turn>,
+    },
+    /// The other Player asks to take back the last exchange of Turns.
+    UndoRequested,
+    /// A request to undo was turned down.
+    UndoDeclined,
+    /// The given number of most recent Turns were taken back.
+    Undone { plies: usize },
+    /// The game ended other than by a Turn, e.g. because a Player resigned.
+    /// `thinking_time` is the total time each Player spent deciding on their
+    /// Turns, by `Player::index`.
+    GameOver {
+        result: GameResult,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thinking_time: Option<[Duration; 2]>,
+    },
+    /// A game of a match ended without deciding it, and the next game
+    /// starts on a fresh board with the given Player moving first. `scores`
+    /// counts the games each Player has won so far, by `Player::index`.
+    NextGame {
+        first_player: Player,
+        scores: [u32; 2],
+    },
+    /// The match is over, won by the given Player, or drawn if `winner` is
+    /// `None` because it ran out of games with the scores level.
+    MatchOver {
+        winner: Option<Player>,
+        scores: [u32; 2],
+    },
+    /// The server couldn't do what the client asked, for the reason given
+    /// by `code`.
+    Error { code: ErrorCode, message: String },
+    /// The status of the game, for clients that aren't part of it.
+    Status {
+        players: usize,
+        spectators: usize,
+        in_progress: bool,
+        board: (usize, usize),
+    },
+}
+
+/// A bidirectional byte stream to the server, whatever transport it uses.
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send + fmt::Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + fmt::Debug> Stream for T {}
+
+/// Where a server can be reached.
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    /// A TCP address.
+    Tcp(SocketAddr),
+    /// The path of a Unix domain socket.
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    /// Open a new stream to the server.
+    pub async fn open(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(match self {
+            Endpoint::Tcp(addr) => Box::new(TcpStream::connect(addr).await?),
+            Endpoint::Unix(path) => Box::new(UnixStream::connect(path).await?),
+        })
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Endpoint::Tcp(addr) => write!(f, "{}", addr),
+            Endpoint::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
 }
 
 /// The connection between the client and server.
 #[derive(Debug)]
 pub struct Connection {
     /// Receive messages from the server as lines.
-    pub lines: Framed<TcpStream, LinesCodec>,
+    pub lines: Framed<Box<dyn Stream>, TranscriptCodec>,
+    /// Where the connection was opened to, if it can be opened again.
+    pub endpoint: Option<Endpoint>,
+    /// How many times to try reopening the connection if it fails.
+    pub reconnect_attempts: u32,
+    /// The token the server gave for reclaiming this client's seat, if any.
+    pub reconnect_token: Option<String>,
+    /// Where every line sent or received is written, if anywhere.
+    pub transcript: Option<Transcript>,
+}
+
+impl Connection {
+    /// Wait for the next Response from the server, skipping any this client
+    /// doesn't recognize, such as ones added by newer servers. Returns
+    /// `None` once the server closes the connection.
+    pub async fn next_response(&mut self) -> Result<Option<Response>, ClientError> {
+        while let Some(line) = self.lines.next().await {
+            let line = line?;
+
+            match serde_json::from_str(&line) {
+                Ok(response) => return Ok(Some(response)),
+                // well-formed, but not a Response this client knows about
+                Err(e) if e.is_data() => {
+                    eprintln!(
+                        "Ignoring an unrecognized response from the server: {}",
+                        line
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Connect to a server listening on the given TCP address.
+    pub async fn connect(addr: SocketAddr) -> Result<Self, ClientError> {
+        Self::connect_to(Endpoint::Tcp(addr)).await
+    }
+
+    /// Connect to a server at the given Endpoint, remembering it so that
+    /// the connection can be reopened.
+    pub async fn connect_to(endpoint: Endpoint) -> Result<Self, ClientError> {
+        let stream = endpoint
+            .open()
+            .await
+            .map_err(|e| ClientError::ConnectionError(e.to_string()))?;
+
+        let mut connection = Self::from_stream(stream);
+        connection.endpoint = Some(endpoint);
+
+        Ok(connection)
+    }
+
+    /// Wrap an already open stream, e.g. one set up by a test. The
+    /// connection can't be reopened if it fails.
+    pub fn from_stream(stream: impl Stream + 'static) -> Self {
+        Connection {
+            lines: Framed::new(Box::new(stream), TranscriptCodec::new(None, "server")),
+            endpoint: None,
+            reconnect_attempts: 0,
+            reconnect_token: None,
+            transcript: None,
+        }
+    }
+
+    /// Write every line sent or received from now on to the given
+    /// Transcript, including after reconnecting.
+    pub fn record_to(&mut self, transcript: Transcript) {
+        let codec = self.lines.codec_mut();
+        codec.set_transcript(Some(transcript.clone()));
+        self.transcript = Some(transcript);
+    }
+
+    /// Reopen the connection and reclaim the given Player's seat. Returns
+    /// the server's snapshot of the game as the encoded board, the Player
+    /// whose turn it is, and the history of Turns.
+    pub async fn reconnect(
+        &mut self,
+        player: Player,
+    ) -> Result<(String, Player, Vec<Turn>), ClientError> {
+        let endpoint = match &self.endpoint {
+            Some(endpoint) => endpoint,
+            None => {
+                return Err(ClientError::ConnectionError(String::from(
+                    "There's nowhere to reconnect to.",
+                )))
+            }
+        };
+        let token = match self.reconnect_token.clone() {
+            Some(token) => token,
+            None => {
+                return Err(ClientError::ConnectionError(String::from(
+                    "The server doesn't hold seats for reconnecting.",
+                )))
+            }
+        };
+        let stream = endpoint
+            .open()
+            .await
+            .map_err(|e| ClientError::ConnectionError(e.to_string()))?;
+
+        let codec = TranscriptCodec::new(self.transcript.clone(), "server");
+        self.lines = Framed::new(stream, codec);
+        self.lines
+            .send(serde_json::to_string(&Request::Rejoin { player, token })?)
+            .await?;
+
+        while let Some(response) = self.next_response().await? {
+            match response {
+                Response::Welcome { player: seat, .. } if seat != player => {
+                    return Err(ClientError::ConnectionError(String::from(
+                        "The server no longer holds this Player's seat.",
+                    )))
+                }
+                Response::Welcome {
+                    reconnect_token, ..
+                } => self.reconnect_token = reconnect_token,
+                Response::BoardState {
+                    board,
+                    current_player,
+                    turns,
+                    ..
+                } => return Ok((board, current_player, turns)),
+                Response::GameFull => return Err(ClientError::GameFull),
+                _ => {}
+            }
+        }
+
+        Err(ClientError::ConnectionError(String::from(
+            "The server closed the connection.",
+        )))
+    }
 }
 
 pub async fn process(
     session: &mut Session,
     connection: &mut Connection,
-) -> Result<(), ClientError> {
+    source: &mut dyn MoveSource,
+) -> Result<GameResult, ClientError> {
     // wait for the `GameStart` response from the server
     loop {
-        match connection.lines.next().await {
-            Some(Ok(ref resp)) => {
-                let response: Response = serde_json::from_str(&resp)?;
-
-                if let Response::GameStart = response {
-                    break;
-                }
+        match connection.next_response().await? {
+            Some(Response::GameStart) => break,
+            // a game that's already under way, e.g. a resumed one, is
+            // caught up on before it goes on
+            Some(Response::BoardState {
+                board,
+                current_player,
+                turns,
+                ..
+            }) => session.catch_up(&board, current_player, &turns)?,
+            Some(Response::IdleTimeout) => {
+                return Err(ClientError::ServerError(String::from(
+                    "The game was closed after going idle.",
+                )))
+            }
+            Some(_) => {}
+            None => {
+                return Err(ClientError::ConnectionError(String::from(
+                    "The server closed the connection.",
+                )))
             }
-            _ => {}
         }
     }
 
     // the response arrived, we can start the game now
-    session.play(connection).await?;
-
-    Ok(())
+    session.play_match(connection, source).await
 }
@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use tokio::io::{self, AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::time;
+
+use crate::{
+    error::ClientError,
+    game::{board::Board, BoardStyle},
+    Turn,
+};
+
+static CONTROLS: &str = "Replay controls:
+  [Enter]  step forward one move
+  b        step back one move
+  c        toggle continuous playback
+  quit     stop the replay
+";
+
+/// Steps through a recorded game one Turn at a time.
+pub struct Replay {
+    /// The Board as of the current position in the replay.
+    pub board: Board,
+    /// How the Board is drawn.
+    pub board_style: BoardStyle,
+    /// Every Turn of the recorded game.
+    turns: Vec<Turn>,
+    /// The coordinates occupied by each Turn applied so far, in order.
+    applied: Vec<(usize, usize)>,
+}
+
+impl Replay {
+    /// Initialize a Replay of the given Turns on an empty Board of the
+    /// specified dimensions.
+    pub fn new(height: usize, width: usize, turns: Vec<Turn>) -> Self {
+        Replay {
+            board: Board::new(height, width),
+            board_style: BoardStyle::Plain,
+            turns,
+            applied: Vec::new(),
+        }
+    }
+
+    /// The number of Turns applied so far.
+    pub fn position(&self) -> usize {
+        self.applied.len()
+    }
+
+    /// Whether every Turn has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.applied.len() == self.turns.len()
+    }
+
+    /// Apply the next Turn. Returns `false` if there are no Turns left.
+    pub fn forward(&mut self) -> Result<bool, ClientError> {
+        let turn = match self.turns.get(self.applied.len()) {
+            Some(turn) => *turn,
+            None => return Ok(false),
+        };
+
+        let coordinates = self.board.apply_turn(&turn)?;
+        self.applied.push(coordinates);
+
+        Ok(true)
+    }
+
+    /// Take back the most recently applied Turn. Returns `false` if no
+    /// Turns have been applied.
+    pub fn back(&mut self) -> Result<bool, ClientError> {
+        match self.applied.pop() {
+            Some((row, col)) => {
+                self.board.undo_move(row, col)?;
+                self.board.last_move = self.applied.last().copied();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn show(&self) {
+        println!("{}", self.board.render(self.board_style));
+        println!("Move {} of {}", self.position(), self.turns.len());
+    }
+
+    /// Run the interactive replay viewer, advancing every `speed` while
+    /// continuous playback is on.
+    pub async fn run(&mut self, speed: Duration) -> Result<(), ClientError> {
+        println!("{}", CONTROLS);
+        self.show();
+
+        // read commands on their own task so playback can tick between them
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(io::stdin()).lines();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut playing = false;
+        let mut ticks = time::interval(speed);
+
+        loop {
+            tokio::select! {
+                _ = ticks.tick(), if playing => {
+                    if !self.forward()? {
+                        playing = false;
+                        println!("End of game.");
+                        continue;
+                    }
+
+                    self.show();
+                }
+
+                command = rx.recv() => {
+                    let command = match command {
+                        Some(command) => command,
+                        None => break,
+                    };
+
+                    match command.trim().to_lowercase().as_str() {
+                        "" => {
+                            if !self.forward()? {
+                                println!("End of game.");
+                                continue;
+                            }
+                        }
+                        "b" => {
+                            if !self.back()? {
+                                println!("Start of game.");
+                                continue;
+                            }
+                        }
+                        "c" => {
+                            playing = !playing;
+                            ticks = time::interval_at(time::Instant::now() + speed, speed);
+                            continue;
+                        }
+                        "quit" => break,
+                        _ => {
+                            println!("{}", CONTROLS);
+                            continue;
+                        }
+                    }
+
+                    self.show();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Move, Player, Side};
+
+    fn turn(source: Player, row: usize, side: Side) -> Turn {
+        Turn {
+            source,
+            mov: Move { side, row },
+        }
+    }
+
+    #[test]
+    fn steps_forward_and_back_through_the_frames() {
+        let turns = vec![
+            turn(Player::First, 0, Side::Left),
+            turn(Player::Second, 0, Side::Right),
+            turn(Player::First, 1, Side::Left),
+        ];
+        let frames = [
+            "____/____/____",
+            "___X/____/____",
+            "O__X/____/____",
+            "O__X/___X/____",
+        ];
+        let mut replay = Replay::new(3, 4, turns);
+
+        assert_eq!(replay.board.encode(), frames[0]);
+        for frame in &frames[1..] {
+            assert!(replay.forward().unwrap());
+            assert_eq!(replay.board.encode(), *frame);
+        }
+        assert!(replay.is_finished());
+        assert!(!replay.forward().unwrap());
+
+        for (position, frame) in frames.iter().enumerate().rev().skip(1) {
+            assert!(replay.back().unwrap());
+            assert_eq!(replay.board.encode(), *frame);
+            assert_eq!(replay.position(), position);
+        }
+        assert!(!replay.back().unwrap());
+        assert_eq!(replay.board.last_move, None);
+
+        // stepping forward again replays the same frames
+        replay.forward().unwrap();
+        assert_eq!(replay.board.encode(), frames[1]);
+        assert_eq!(replay.board.last_move, Some((0, 3)));
+    }
+}
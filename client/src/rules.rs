@@ -0,0 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+static WELCOME_EN: &str = "Welcome to SideStacker!
+On your turn, specify your move with the format
+`[ROW-NUMBER][SIDE]` with no spaces in between.
+
+The following are examples of valid moves:
+2R, 5r, 1l, 3L.
+
+Type `undo` to take back the last exchange of
+moves, `surrender` to resign the game, or `quit`
+to leave it.
+
+The game ends when there are no spaces left
+available, or when a player has enough consecutive
+pieces on a diagonal, column, or row.
+";
+
+static WELCOME_ES: &str = "¡Bienvenido a SideStacker!
+En tu turno, indica tu jugada con el formato
+`[NÚMERO-DE-FILA][LADO]` sin espacios entre ellos.
+
+Estos son ejemplos de jugadas válidas:
+2R, 5r, 1l, 3L.
+
+Escribe `undo` para deshacer el último intercambio
+de jugadas, `surrender` para rendirte, o `quit`
+para salir de la partida.
+
+La partida termina cuando no quedan espacios
+libres, o cuando un jugador tiene suficientes
+fichas seguidas en una diagonal, columna o fila.
+";
+
+static WELCOME_FR: &str = "Bienvenue dans SideStacker !
+À votre tour, indiquez votre coup au format
+`[NUMÉRO-DE-LIGNE][CÔTÉ]` sans espace entre les deux.
+
+Voici des exemples de coups valides :
+2R, 5r, 1l, 3L.
+
+Tapez `undo` pour annuler le dernier échange de
+coups, `surrender` pour abandonner, ou `quit`
+pour quitter la partie.
+
+La partie se termine lorsqu'il ne reste plus de
+cases libres, ou lorsqu'un joueur aligne assez de
+pions sur une diagonale, une colonne ou une ligne.
+";
+
+/// The languages the rules of the game are bundled in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Lang {
+    /// English.
+    #[default]
+    En,
+    /// Spanish.
+    Es,
+    /// French.
+    Fr,
+}
+
+impl Lang {
+    /// The rules of the game, as shown when a game starts, in this
+    /// language.
+    pub fn rules_text(self) -> &'static str {
+        match self {
+            Lang::En => WELCOME_EN,
+            Lang::Es => WELCOME_ES,
+            Lang::Fr => WELCOME_FR,
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "es" => Ok(Lang::Es),
+            "fr" => Ok(Lang::Fr),
+            _ => Err(format!(
+                "Unknown language '{}', expected 'en', 'es', or 'fr'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lang::En => write!(f, "en"),
+            Lang::Es => write!(f, "es"),
+            Lang::Fr => write!(f, "fr"),
+        }
+    }
+}
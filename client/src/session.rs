@@ -1,24 +1,55 @@
-use futures::{sink::SinkExt, StreamExt};
+use futures::sink::SinkExt;
 use std::convert::TryFrom;
-use std::io::{self, prelude::*};
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::time::{sleep, timeout};
 
 use crate::{
+    ai::Strategy,
     error::ClientError,
-    game::{board::Board, Slot},
-    Connection, Move, Player, Response, Side, Turn,
+    game::{board::Board, BoardStyle, Slot},
+    input::MoveSource,
+    rules::Lang,
+    Connection, ErrorCode, GameResult, Move, Player, Request, Response, Ruleset, Turn,
 };
 
-static WELCOME: &str = "Welcome to SideStacker!
-On your turn, specify your move with the format 
-`[ROW-NUMBER][SIDE]` with no spaces in between.
+/// How long to wait before each attempt to reconnect to the server.
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
 
-The following are examples of valid moves:
-2R, 5r, 1l, 3L.
+/// How long to wait for the other Player to answer a request to undo.
+const UNDO_TIMEOUT: Duration = Duration::from_secs(60);
 
-The game ends when there are no spaces left 
-available, or when a player has four consecutive
-pieces on a diagonal, column, or row.
-";
+/// What a Player entered at the move prompt.
+enum Input {
+    /// A Move to play.
+    Move(Move),
+    /// Take back the last exchange of Moves.
+    Undo,
+    /// Resign the game to the other Player.
+    Surrender,
+    /// Leave the game.
+    Quit,
+}
+
+/// How the game stands after a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// The game goes on.
+    Continue,
+    /// The game is over, ending as given.
+    Over(GameResult),
+}
+
+/// What came of a single step of the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepResult {
+    /// Whether the game goes on, or how it ended.
+    pub outcome: Outcome,
+    /// Whether anything was played on the Board, or the Board was replaced
+    /// after reconnecting.
+    pub board_changed: bool,
+}
 
 /// The client's view of the game.
 pub struct Session {
@@ -30,129 +61,1088 @@ pub struct Session {
     pub current_player: Player,
     /// The turns that have occurred over the course of the game.
     pub turns: Vec<Turn>,
+    /// The rules of the game, as agreed with the server.
+    pub ruleset: Ruleset,
+    /// The explanation of how to play shown when the game starts.
+    pub rules_text: String,
+    /// Whether this client plays both sides of the game.
+    pub self_play: bool,
+    /// Whether to show the position each Move leads to and ask before
+    /// playing it.
+    pub confirm_moves: bool,
+    /// Chooses this client's Moves in place of its input, if set. Its input
+    /// is still used to answer questions, like whether to agree to undo.
+    pub auto_move: Option<Box<dyn Strategy + Send>>,
+    /// How the Board is drawn.
+    pub board_style: BoardStyle,
+    /// Whether to print each Move played to stderr as it would be typed,
+    /// e.g. `First 3R`.
+    pub echo_moves: bool,
+    /// How many games the match being played lasts at most, if this game
+    /// is one of a series.
+    pub best_of: Option<u32>,
+    /// The coordinates each Turn landed at, in the order they were taken.
+    placed: Vec<(usize, usize)>,
+    /// The Board the game started from, if it didn't start from an empty
+    /// one. Every game of a match starts from it.
+    start_position: Option<Board>,
 }
 
 impl Session {
-    /// Initialize a new Session with a Board of the specified dimensions.
-    pub fn new(player: Player, height: usize, width: usize) -> Self {
+    /// Initialize a new Session played under the given Ruleset.
+    pub fn new(player: Player, ruleset: Ruleset) -> Self {
         Session {
-            board: Board::new(height, width),
+            board: Board::with_ruleset(&ruleset),
             turns: Vec::new(),
             player,
-            current_player: Player::First,
+            current_player: ruleset.first_player,
+            ruleset,
+            rules_text: Lang::default().rules_text().to_string(),
+            self_play: false,
+            confirm_moves: false,
+            auto_move: None,
+            board_style: BoardStyle::Plain,
+            echo_moves: false,
+            best_of: None,
+            placed: Vec::new(),
+            start_position: None,
+        }
+    }
+
+    /// Replace the game so far with the given history of Turns, played from
+    /// the game's starting position, so that play can pick up where it left
+    /// off. Nothing changes if any Turn can't be played.
+    pub fn replay_into(&mut self, turns: &[Turn]) -> Result<(), ClientError> {
+        let mut board = match &self.start_position {
+            Some(board) => board.clone(),
+            None => Board::with_ruleset(&self.ruleset),
+        };
+        let mut placed = Vec::with_capacity(turns.len());
+
+        for turn in turns {
+            placed.push(board.apply_turn(turn)?);
+        }
+
+        self.current_player = match turns.last() {
+            Some(turn) => !turn.source,
+            None => self.ruleset.first_player,
+        };
+        self.board = board;
+        self.turns = turns.to_vec();
+        self.placed = placed;
+
+        Ok(())
+    }
+
+    /// Start the game from the given encoded Board instead of an empty one,
+    /// with whoever's turn it is in that position to move.
+    pub fn start_from(&mut self, encoded: &str) -> Result<(), ClientError> {
+        let board = self.decode_board(encoded)?;
+
+        self.current_player = board
+            .to_move(self.ruleset.first_player)
+            .ok_or(ClientError::MalformedBoard)?;
+        self.ruleset.height = board.height;
+        self.ruleset.width = board.width;
+        self.start_position = Some(board.clone());
+        self.board = board;
+        self.turns.clear();
+        self.placed.clear();
+
+        Ok(())
+    }
+
+    /// Clear the game away for the next game of a match, which the given
+    /// Player opens.
+    pub fn next_game(&mut self, first_player: Player) {
+        self.ruleset.first_player = first_player;
+        self.board = match &self.start_position {
+            Some(board) => board.clone(),
+            None => Board::with_ruleset(&self.ruleset),
+        };
+        self.current_player = self.board.to_move(first_player).unwrap_or(first_player);
+        self.turns.clear();
+        self.placed.clear();
+    }
+
+    /// Start the game from the encoded Board saved in the given file. Whose
+    /// turn it is follows from how many pieces each Player has placed.
+    pub fn start_from_file(&mut self, path: &Path) -> Result<(), ClientError> {
+        self.start_from(&std::fs::read_to_string(path)?)
+    }
+
+    /// Decode a Board sent by the server, to be played under this game's
+    /// Ruleset.
+    fn decode_board(&self, encoded: &str) -> Result<Board, ClientError> {
+        let mut board = Board::decode(encoded)?;
+        board.win_length = self.ruleset.win_length;
+        board.gravity = self.ruleset.gravity;
+        board.swap_symbols = self.ruleset.swap_symbols;
+        board.side_restrictions = self.ruleset.side_restrictions.clone();
+
+        Ok(board)
+    }
+
+    /// The Slot the Player whose turn it is plays.
+    pub fn current_symbol(&self) -> Slot {
+        self.current_player.symbol(self.ruleset.swap_symbols)
+    }
+
+    /// Make sure a Board sent by the server has the same dimensions as the
+    /// one this client is playing on, so that the two never disagree about
+    /// which Moves can be played.
+    fn check_dimensions(&self, board: &Board) -> Result<(), ClientError> {
+        let server = (board.height, board.width);
+        let client = (self.board.height, self.board.width);
+
+        if server != client {
+            return Err(ClientError::BoardMismatch { server, client });
         }
+
+        Ok(())
     }
 
-    /// Run the game loop.
-    pub async fn play(&mut self, connection: &mut Connection) -> Result<(), ClientError> {
-        println!("{}", WELCOME);
+    /// Work out the Board the current Player's Move would lead to, along with
+    /// the coordinates where its piece lands, without changing the game.
+    pub fn board_after_move(&self, mov: &Move) -> Result<(Board, (usize, usize)), ClientError> {
+        let mut board = self.board.clone();
+        let turn = Turn {
+            source: self.current_player,
+            mov: *mov,
+        };
+        let landed = board.apply_turn(&turn)?;
+
+        Ok((board, landed))
+    }
+
+    /// Run the game loop with the local Player's input read from `source`,
+    /// returning how the game ended.
+    ///
+    /// A Move that can't be played, because its row is full or doesn't
+    /// exist or because it isn't the Player's turn, never uses up their
+    /// turn: the same Player is asked for another Move.
+    pub async fn play(
+        &mut self,
+        connection: &mut Connection,
+        source: &mut dyn MoveSource,
+    ) -> Result<GameResult, ClientError> {
+        println!("{}", self.rules_text);
+        println!("{} consecutive pieces win.", self.ruleset.win_length);
+        let mut restrictions = self.ruleset.side_restrictions.iter().collect::<Vec<_>>();
+        restrictions.sort_by_key(|(row, _)| **row);
+        for (row, side) in restrictions {
+            println!("Row {} can only be played from the {}.", row, side.name());
+        }
+        println!("You're playing {}.\n", self.board.slot_for(self.player));
 
         loop {
-            // Check if the game has resulted in a tie
-            if self.turns.len() == self.board.height * self.board.width {
+            // Check if the game has resulted in a tie, which it has as soon
+            // as nobody can complete a line, even before the Board is full
+            if !self.board.can_anyone_win() {
                 println!("Game ended in a tie!");
-                break;
+                return Ok(GameResult::Tie);
             }
 
-            println!("{}", self.board);
-            println!("{} player's turn:", self.current_player);
-            println!("What's the move?");
+            // wait for the other Player to take their Turn
+            if self.current_player != self.player && !self.self_play {
+                match self.await_opponent(connection, source).await {
+                    Ok(Some(result)) => return Ok(result),
+                    Ok(None) => {}
+                    Err(e) => self.resync(connection, e).await?,
+                }
+                continue;
+            }
 
-            io::stdout()
-                .flush()
-                .map_err(|e| ClientError::InputError { source: e })?;
+            let mov = match self.prompt(source)? {
+                Input::Move(mov) => mov,
+                Input::Undo => {
+                    match self.negotiate_undo(connection, source).await {
+                        Ok(Some(result)) => return Ok(result),
+                        Ok(None) => {}
+                        Err(e) => self.resync(connection, e).await?,
+                    }
+                    continue;
+                }
+                Input::Surrender => return self.surrender(connection).await,
+                Input::Quit => {
+                    // close the connection cleanly so the server sees an
+                    // ordinary disconnect
+                    SinkExt::<String>::close(&mut connection.lines).await?;
+                    return Ok(GameResult::LocalQuit);
+                }
+            };
+
+            if let Outcome::Over(result) = self.step(connection, mov, source).await?.outcome {
+                return Ok(result);
+            }
+        }
+    }
 
-            let mut input = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .map_err(|e| ClientError::InputError { source: e })?;
+    /// Play every game of the match this game is part of, as `play` does,
+    /// moving on to each next game as the server starts it. Returns how the
+    /// match ended, as a win for whoever won it or a tie if it was drawn,
+    /// or how the last game ended if the match was cut short. Outside of a
+    /// match, this plays the one game.
+    pub async fn play_match(
+        &mut self,
+        connection: &mut Connection,
+        source: &mut dyn MoveSource,
+    ) -> Result<GameResult, ClientError> {
+        loop {
+            let result = self.play(connection, source).await?;
 
-            if input.trim().to_lowercase() == "quit" {
-                break;
+            // only a game that was played out leads on to another
+            let finished = matches!(
+                result,
+                GameResult::Win(_) | GameResult::Tie | GameResult::Resigned(_)
+            );
+            if self.best_of.is_none() || !finished {
+                return Ok(result);
             }
 
-            // parse the input into a Move
-            let mov = match Move::try_from(input) {
-                Ok(mov) => mov,
+            if let Some(result) = self.await_next_game(connection).await? {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Wait for the server to either start the next game of the match,
+    /// getting the Board ready for it, or say how the match ended. Returns
+    /// the match's result once it's over.
+    async fn await_next_game(
+        &mut self,
+        connection: &mut Connection,
+    ) -> Result<Option<GameResult>, ClientError> {
+        loop {
+            match next_response(connection).await? {
+                Response::NextGame {
+                    first_player,
+                    scores,
+                } => {
+                    println!(
+                        "The match stands at {} to {}. {} Player opens the next game.",
+                        scores[0], scores[1], first_player
+                    );
+                    self.next_game(first_player);
+                    return Ok(None);
+                }
+                Response::MatchOver { winner, scores } => {
+                    return Ok(Some(match winner {
+                        Some(winner) => {
+                            println!(
+                                "Match won by {} Player, {} to {}!",
+                                winner, scores[0], scores[1]
+                            );
+                            GameResult::Win(winner)
+                        }
+                        None => {
+                            println!("The match ended in a draw, {} to {}.", scores[0], scores[1]);
+                            GameResult::Tie
+                        }
+                    }));
+                }
+                Response::PlayerDisconnected => {
+                    println!("Your opponent left the game.");
+                    return Ok(Some(GameResult::OpponentLeft));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Play a single Move for the local Player: submit it, wait for the
+    /// server to acknowledge it, and then, unless this client plays both
+    /// sides, wait for the other Player's reply. Questions the game asks in
+    /// the meantime are answered from `source`. Lets embedders and tests
+    /// drive a game one Move at a time, as `play` does.
+    ///
+    /// A Move that can't be played is reported and leaves the game as it
+    /// was, for the caller to try another.
+    pub async fn step(
+        &mut self,
+        connection: &mut Connection,
+        mov: Move,
+        source: &mut dyn MoveSource,
+    ) -> Result<StepResult, ClientError> {
+        let unchanged = StepResult {
+            outcome: Outcome::Continue,
+            board_changed: false,
+        };
+        let changed = StepResult {
+            outcome: Outcome::Continue,
+            board_changed: true,
+        };
+        let over = |result| StepResult {
+            outcome: Outcome::Over(result),
+            board_changed: true,
+        };
+
+        let turn = Turn {
+            source: if self.self_play {
+                self.current_player
+            } else {
+                self.player
+            },
+            mov,
+        };
+
+        // don't send a Move the Board can't take
+        if let Err(e) = self.board.clone().apply_turn(&turn) {
+            print_retry(&e);
+            return Ok(unchanged);
+        }
+
+        let request = serde_json::to_string(&Request::Turn(turn))?;
+        if let Err(e) = connection.lines.send(&request).await {
+            self.resync(connection, e.into()).await?;
+            return Ok(changed);
+        }
+
+        let (coordinate, game_over, next_player) = loop {
+            let response = match next_response(connection).await {
+                Ok(response) => response,
                 Err(e) => {
-                    println!("{}", e);
-                    continue;
+                    // the Turn may or may not have been played, but the
+                    // server's snapshot says which
+                    self.resync(connection, e).await?;
+                    return Ok(changed);
                 }
             };
 
-            let turn = Turn {
-                source: self.player,
-                mov,
-            };
-            connection
-                .lines
-                .send(&serde_json::to_string(&turn)?)
-                .await?;
-
-            loop {
-                match connection.lines.next().await {
-                    Some(Ok(ref resp)) => {
-                        let response: Response = serde_json::from_str(&resp)?;
-
-                        if let Response::Acknowledged = response {
-                            break;
-                        }
+            match response {
+                Response::Acknowledged {
+                    coordinate,
+                    game_over,
+                    next_player,
+                    ..
+                } => break (coordinate, game_over, next_player),
+                Response::Error { code, message } => {
+                    println!("{}", message);
+                    match code {
+                        ErrorCode::InvalidMove => println!("Please pick a different move."),
+                        ErrorCode::NotYourTurn => println!("Wait for your opponent to move."),
+                        ErrorCode::RateLimited => println!("Slow down before trying again."),
+                        ErrorCode::Internal | ErrorCode::Forbidden | ErrorCode::ServerFull => {}
+                    }
+                    return Ok(unchanged);
+                }
+                response => {
+                    if let Some(result) = self.update(response, connection, source).await? {
+                        return Ok(over(result));
                     }
-                    _ => {}
                 }
             }
+        };
 
-            let slot = match self.current_player {
-                Player::First => Slot::X,
-                Player::Second => Slot::O,
-            };
+        if let Some(result) = self.accept_turn(turn, coordinate, game_over, next_player)? {
+            return Ok(over(result));
+        }
 
-            // update the Board state
-            let (row, col) = match mov.side {
-                Side::Left => match self.board.insert_from_left(mov.row, slot) {
-                    Err(e) => {
-                        println!("{}", e);
-                        continue;
+        if self.current_player != self.player && !self.self_play && !self.board.is_full() {
+            match self.await_opponent(connection, source).await {
+                Ok(Some(result)) => return Ok(over(result)),
+                Ok(None) => {}
+                Err(e) => self.resync(connection, e).await?,
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Wait for the other Player to take their Turn and play it on the
+    /// Board. Returns the game's result if it ended in the meantime.
+    async fn await_opponent(
+        &mut self,
+        connection: &mut Connection,
+        source: &mut dyn MoveSource,
+    ) -> Result<Option<GameResult>, ClientError> {
+        println!("Waiting for {} player's move...", self.current_player);
+
+        loop {
+            match next_response(connection).await? {
+                Response::Turn(turn) => return self.finish_turn(turn),
+                Response::Played { turn, next_player } => {
+                    let result = self.finish_turn(turn)?;
+                    if result.is_none() {
+                        self.current_player = next_player;
                     }
-                    Ok((row, col)) => (row, col),
-                },
-                Side::Right => match self.board.insert_from_right(mov.row, slot) {
-                    Err(e) => {
-                        println!("{}", e);
-                        continue;
+                    return Ok(result);
+                }
+                response => {
+                    if let Some(result) = self.update(response, connection, source).await? {
+                        return Ok(Some(result));
                     }
-                    Ok((row, col)) => (row, col),
-                },
-            };
+                }
+            }
+        }
+    }
 
-            self.turns.push(turn);
+    /// Handle a Response that can arrive whoever's turn it is. Returns the
+    /// game's result if the Response ended it.
+    async fn update(
+        &mut self,
+        response: Response,
+        connection: &mut Connection,
+        source: &mut dyn MoveSource,
+    ) -> Result<Option<GameResult>, ClientError> {
+        match response {
+            Response::UndoRequested => {
+                let accept = confirm(
+                    source,
+                    "Your opponent asks to undo the last exchange. Agree? [y/N]",
+                )?;
+                let reply = Request::UndoReply { accept };
+                connection
+                    .lines
+                    .send(serde_json::to_string(&reply)?)
+                    .await?;
+            }
+            Response::Undone { plies } => {
+                self.undo(plies)?;
+            }
+            Response::BoardState { board, .. } => {
+                self.check_dimensions(&self.decode_board(&board)?)?;
+            }
+            Response::PlayerDisconnected => {
+                println!("Your opponent left the game.");
+                return Ok(Some(GameResult::OpponentLeft));
+            }
+            Response::GameOver {
+                result,
+                thinking_time,
+            } => {
+                if let GameResult::Resigned(player) = result {
+                    println!("{} Player resigned.", player);
+                }
+                if let Some([first, second]) = thinking_time {
+                    println!(
+                        "Thinking time: {} Player {}s, {} Player {}s.",
+                        Player::First,
+                        first.as_secs(),
+                        Player::Second,
+                        second.as_secs()
+                    );
+                }
+                return Ok(Some(result));
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
 
-            // check if the game is over
-            match self.board.is_game_over(row, col, &slot) {
-                Ok(slot) => match slot {
-                    Some(Slot::X) => {
-                        println!("Game won by First Player!");
-                        break;
+    /// Run the game loop against a computer opponent, without a server, with
+    /// the local Player's input read from `source`. Returns how the game
+    /// ended. Moves that can't be played are handled as in `play`.
+    pub fn play_offline(
+        &mut self,
+        opponent: &mut dyn Strategy,
+        source: &mut dyn MoveSource,
+    ) -> Result<GameResult, ClientError> {
+        println!("{}", self.rules_text);
+        println!("{} consecutive pieces win.\n", self.ruleset.win_length);
+
+        loop {
+            // Check if the game has resulted in a tie, which it has as soon
+            // as nobody can complete a line, even before the Board is full
+            if !self.board.can_anyone_win() {
+                println!("Game ended in a tie!");
+                return Ok(GameResult::Tie);
+            }
+
+            let mov = if self.current_player == self.player {
+                match self.prompt(source)? {
+                    Input::Move(mov) => mov,
+                    Input::Undo => {
+                        // take back the computer's reply along with the
+                        // Player's own Move
+                        if self.undo(2)? {
+                            println!("Took back the last exchange of moves.");
+                        } else {
+                            println!("There's nothing to undo yet.");
+                        }
+                        continue;
                     }
-                    Some(Slot::O) => {
-                        println!("Game won by Second Player!");
-                        break;
+                    Input::Surrender => {
+                        println!("You resigned.");
+                        return Ok(GameResult::Resigned(self.player));
                     }
-                    Some(Slot::Blank) => {
-                        panic!("Returned a blank Slot where it should not have been returned.")
+                    Input::Quit => return Ok(GameResult::LocalQuit),
+                }
+            } else {
+                match opponent.choose_move(&self.board, self.current_player) {
+                    Some(mov) => {
+                        println!("The computer plays {}", mov);
+                        mov
                     }
-                    None => {
-                        self.current_player = !self.current_player;
-                        continue;
+                    // the computer only runs out of Moves once the Board
+                    // is full
+                    None => return Ok(GameResult::Tie),
+                }
+            };
+
+            let turn = Turn {
+                source: self.current_player,
+                mov,
+            };
+
+            match self.finish_turn(turn) {
+                Ok(Some(result)) => return Ok(result),
+                Ok(None) => {}
+                Err(e) => print_retry(&e),
+            }
+        }
+    }
+
+    /// Show the Board and ask the current Player for their Move until they
+    /// enter one that parses or a command. Running out of input counts as
+    /// quitting.
+    fn prompt(&mut self, source: &mut dyn MoveSource) -> Result<Input, ClientError> {
+        loop {
+            println!("{}", self.board.render(self.board_style));
+            println!(
+                "{} of {} cells filled",
+                self.board.count_pieces(),
+                self.board.capacity()
+            );
+            println!(
+                "{} player's turn ({}):",
+                self.current_player,
+                self.current_symbol()
+            );
+            println!("What's the move?");
+
+            if let Some(strategy) = &mut self.auto_move {
+                let choice = strategy.choose_move(&self.board, self.current_player);
+
+                return Ok(match choice {
+                    Some(mov) => {
+                        println!("{}", mov);
+                        Input::Move(mov)
                     }
-                },
+                    // there's nothing left to play
+                    None => Input::Quit,
+                });
+            }
+
+            let input = match source.next_line()? {
+                Some(input) => input,
+                None => return Ok(Input::Quit),
+            };
+
+            match input.trim().to_lowercase().as_str() {
+                "quit" => return Ok(Input::Quit),
+                "undo" => return Ok(Input::Undo),
+                "surrender" => return Ok(Input::Surrender),
+                _ => {}
+            }
+
+            // parse the input into a Move
+            let mov = match Move::try_from(input) {
+                Ok(mov) => mov,
                 Err(e) => {
-                    println!("{}", e);
+                    print_retry(&e);
                     continue;
                 }
+            };
+
+            if !self.confirm_moves || self.confirm_move(mov, source)? {
+                return Ok(Input::Move(mov));
             }
         }
+    }
+
+    /// Show the position the given Move would lead to and ask the current
+    /// Player whether to play it.
+    fn confirm_move(&self, mov: Move, source: &mut dyn MoveSource) -> Result<bool, ClientError> {
+        let board = match self.board_after_move(&mov) {
+            Ok((board, _)) => board,
+            Err(e) => {
+                print_retry(&e);
+                return Ok(false);
+            }
+        };
+
+        println!("{}", board.render(self.board_style));
+        confirm(source, "Play this move? [y/N]")
+    }
+
+    /// Record a Turn exactly as the server reports it was played, rather than
+    /// working out its outcome locally. Returns the game's result if the Turn
+    /// ended it.
+    fn accept_turn(
+        &mut self,
+        turn: Turn,
+        (row, col): (usize, usize),
+        game_over: Option<Option<Player>>,
+        next_player: Player,
+    ) -> Result<Option<GameResult>, ClientError> {
+        let slot = self.board.slot_for(turn.source);
+
+        self.board.set_slot(row, col, slot)?;
+        self.record_turn(turn, (row, col));
+        self.current_player = next_player;
+
+        Ok(match game_over {
+            Some(Some(winner)) => {
+                println!("Game won by {} Player!", winner);
+                Some(GameResult::Win(winner))
+            }
+            Some(None) => {
+                println!("Game ended in a tie!");
+                Some(GameResult::Tie)
+            }
+            None => None,
+        })
+    }
+
+    /// Ask the other Player to agree to take back the last exchange of
+    /// Moves, and wait for their answer. Anything else that happens in the
+    /// meantime is handled as usual, and returns the game's result if it
+    /// ended the game. Stops waiting after `UNDO_TIMEOUT`, leaving a late
+    /// answer to be handled like any other Response.
+    async fn negotiate_undo(
+        &mut self,
+        connection: &mut Connection,
+        source: &mut dyn MoveSource,
+    ) -> Result<Option<GameResult>, ClientError> {
+        connection
+            .lines
+            .send(serde_json::to_string(&Request::Undo)?)
+            .await?;
+        println!("Asked your opponent to undo the last exchange...");
+
+        loop {
+            let response = match timeout(UNDO_TIMEOUT, next_response(connection)).await {
+                Ok(response) => response?,
+                Err(_) => {
+                    println!("Your opponent didn't answer, so nothing was undone.");
+                    return Ok(None);
+                }
+            };
+
+            match response {
+                Response::Undone { plies } => {
+                    self.undo(plies)?;
+                    println!("Your opponent agreed. Took back the last exchange of moves.");
+                    return Ok(None);
+                }
+                Response::UndoDeclined => {
+                    println!("Your opponent declined to undo.");
+                    return Ok(None);
+                }
+                Response::Error { message, .. } => {
+                    println!("{}", message);
+                    return Ok(None);
+                }
+                response => {
+                    if let Some(result) = self.update(response, connection, source).await? {
+                        return Ok(Some(result));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconnect to the server after the connection failed with the given
+    /// error, and catch up with the game from the server's snapshot of it.
+    /// Returns the original error if it can't be recovered from or every
+    /// attempt to reconnect fails.
+    async fn resync(
+        &mut self,
+        connection: &mut Connection,
+        error: ClientError,
+    ) -> Result<(), ClientError> {
+        if !error.is_recoverable() {
+            return Err(error);
+        }
+
+        for attempt in 1..=connection.reconnect_attempts {
+            println!(
+                "Lost the connection to the server, reconnecting ({}/{})...",
+                attempt, connection.reconnect_attempts
+            );
+            sleep(RECONNECT_DELAY).await;
+
+            let (board, current_player, turns) = match connection.reconnect(self.player).await {
+                Ok(snapshot) => snapshot,
+                Err(e) if e.is_recoverable() => continue,
+                Err(e) => return Err(e),
+            };
+
+            self.catch_up(&board, current_player, &turns)?;
+            println!("Reconnected.");
+
+            return Ok(());
+        }
+
+        Err(error)
+    }
+
+    /// Catch up with a game from the server's snapshot of it: the encoded
+    /// board, the Player whose turn it is, and the history of Turns. The
+    /// history is replayed unless it doesn't lead to the snapshot's board,
+    /// in which case it's dropped and Turns from before the snapshot can't
+    /// be undone.
+    pub fn catch_up(
+        &mut self,
+        board: &str,
+        current_player: Player,
+        turns: &[Turn],
+    ) -> Result<(), ClientError> {
+        let board = self.decode_board(board)?;
+        self.check_dimensions(&board)?;
+
+        let replayed = self.replay_into(turns).is_ok() && self.board.encode() == board.encode();
+        if !replayed {
+            self.board = board;
+            self.turns.clear();
+            self.placed.clear();
+        }
+        self.current_player = current_player;
 
         Ok(())
     }
+
+    /// Resign the game, and wait for the server to confirm that it's over
+    /// before leaving.
+    async fn surrender(&mut self, connection: &mut Connection) -> Result<GameResult, ClientError> {
+        connection
+            .lines
+            .send(serde_json::to_string(&Request::Resign)?)
+            .await?;
+
+        loop {
+            if let Response::GameOver { result, .. } = next_response(connection).await? {
+                println!("You resigned.");
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Take back the given number of most recent Turns, handing the move
+    /// back to whoever took each one. Returns `false`, changing nothing, if
+    /// fewer Turns than that have been taken.
+    fn undo(&mut self, plies: usize) -> Result<bool, ClientError> {
+        if plies > self.placed.len() {
+            return Ok(false);
+        }
+
+        for _ in 0..plies {
+            if let Some((row, col)) = self.placed.pop() {
+                self.board.undo_move(row, col)?;
+            }
+            if let Some(turn) = self.turns.pop() {
+                self.current_player = turn.source;
+            }
+        }
+        self.board.last_move = self.placed.last().copied();
+
+        Ok(true)
+    }
+
+    /// Add a Turn that has been played on the Board, at the given
+    /// coordinates, to the game's history.
+    fn record_turn(&mut self, turn: Turn, coordinates: (usize, usize)) {
+        self.turns.push(turn);
+        self.placed.push(coordinates);
+
+        if self.echo_moves {
+            eprintln!("{} {}", turn.source, turn.mov.to_command());
+        }
+    }
+
+    /// Play the Turn on the Board and hand over to the next Player.
+    /// Returns the game's result if the Turn ended it. A Turn that can't be
+    /// played leaves the Session as it was.
+    fn finish_turn(&mut self, turn: Turn) -> Result<Option<GameResult>, ClientError> {
+        let slot = self.board.slot_for(turn.source);
+
+        // update the Board state
+        let (row, col) = self.board.apply_turn(&turn)?;
+        let over = self.board.is_game_over(row, col, &slot);
+        self.record_turn(turn, (row, col));
+
+        // check if the game is over, handing over to the next Player
+        // unless it is
+        match over.map(|slot| slot.and_then(|slot| self.board.owner(slot))) {
+            Ok(Some(winner)) => {
+                println!("Game won by {} Player!", winner);
+                Ok(Some(GameResult::Win(winner)))
+            }
+            other => {
+                self.current_player = !self.current_player;
+                other.map(|_| None)
+            }
+        }
+    }
+}
+
+/// Ask the Player a yes or no question, defaulting to no.
+fn confirm(source: &mut dyn MoveSource, question: &str) -> Result<bool, ClientError> {
+    println!("{}", question);
+
+    Ok(source
+        .next_line()?
+        .is_some_and(|input| input.trim().eq_ignore_ascii_case("y")))
+}
+
+/// Wait for the server's next Response.
+async fn next_response(connection: &mut Connection) -> Result<Response, ClientError> {
+    connection.next_response().await?.ok_or_else(|| {
+        ClientError::ConnectionError(String::from("The server closed the connection."))
+    })
+}
+
+/// Tell the Player that the Move they entered can't be played, with a short
+/// hint if there is one and the full error otherwise.
+fn print_retry(error: &ClientError) {
+    match error.retry_hint() {
+        Some(hint) => println!("{}", hint),
+        None => println!("{}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::Scripted, Side};
+    use futures::StreamExt;
+    use tokio_util::codec::{Framed, LinesCodec};
+
+    /// Always plays into the last Row from the left.
+    struct LastRow;
+
+    impl Strategy for LastRow {
+        fn choose_move(&mut self, board: &Board, _player: Player) -> Option<Move> {
+            Some(Move {
+                side: Side::Left,
+                row: board.height - 1,
+            })
+        }
+    }
+
+    fn script(lines: &[&str]) -> Scripted {
+        Scripted(lines.iter().map(|line| line.to_string()).collect())
+    }
+
+    #[test]
+    fn offline_undo_takes_back_the_last_exchange() {
+        let mut session = Session::new(Player::First, Ruleset::default());
+        let mut input = script(&["0L", "0L", "undo", "quit"]);
+
+        let result = session.play_offline(&mut LastRow, &mut input).unwrap();
+
+        assert_eq!(result, GameResult::LocalQuit);
+        // only the first exchange is left
+        let mut expected = Board::with_ruleset(&session.ruleset);
+        expected.insert_from_left(0, Slot::X).unwrap();
+        expected.insert_from_left(6, Slot::O).unwrap();
+        assert_eq!(session.board.encode(), expected.encode());
+        assert_eq!(session.turns.len(), 2);
+        assert_eq!(session.current_player, Player::First);
+    }
+
+    #[tokio::test]
+    async fn undo_request_ends_with_the_game() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut connection = Connection::from_stream(client);
+        let mut server = Framed::new(server, LinesCodec::new());
+        let mut session = Session::new(Player::First, Ruleset::default());
+
+        let left = serde_json::to_string(&Response::PlayerDisconnected).unwrap();
+        server.send(left).await.unwrap();
+
+        let result = session
+            .negotiate_undo(&mut connection, &mut Scripted::default())
+            .await
+            .unwrap();
+        assert_eq!(result, Some(GameResult::OpponentLeft));
+    }
+
+    #[test]
+    fn replayed_history_is_ready_for_the_next_move() {
+        let mut session = Session::new(Player::First, Ruleset::default());
+        let turns = [
+            Turn {
+                source: Player::First,
+                mov: Move {
+                    side: Side::Left,
+                    row: 0,
+                },
+            },
+            Turn {
+                source: Player::Second,
+                mov: Move {
+                    side: Side::Right,
+                    row: 1,
+                },
+            },
+        ];
+
+        session.replay_into(&turns).unwrap();
+
+        assert_eq!(session.current_player, Player::First);
+        assert_eq!(session.turns, turns.to_vec());
+        assert_eq!(
+            session.board.encode(),
+            Board::from_turns(7, 7, &turns).unwrap().encode()
+        );
+        let next = Move {
+            side: Side::Left,
+            row: 0,
+        };
+        assert_eq!(session.board_after_move(&next).unwrap().1, (0, 5));
+
+        // the replayed Turns can be taken back like any others
+        assert!(session.undo(2).unwrap());
+        assert_eq!(session.board.count_pieces(), 0);
+    }
+
+    #[test]
+    fn unplayable_history_changes_nothing() {
+        let mut session = Session::new(Player::First, Ruleset::default());
+        let off_the_board = Turn {
+            source: Player::First,
+            mov: Move {
+                side: Side::Left,
+                row: 9,
+            },
+        };
+
+        assert!(session.replay_into(&[off_the_board]).is_err());
+        assert!(session.turns.is_empty());
+        assert_eq!(session.board.count_pieces(), 0);
+    }
+
+    #[tokio::test]
+    async fn declined_move_is_never_sent() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut connection = Connection::from_stream(client);
+        let mut server = Framed::new(server, LinesCodec::new());
+        let mut session = Session::new(Player::First, Ruleset::default());
+        session.confirm_moves = true;
+        let mut input = script(&["0L", "n", "quit"]);
+
+        let result = session.play(&mut connection, &mut input).await.unwrap();
+
+        // the Player is asked for another Move, and quits instead
+        assert_eq!(result, GameResult::LocalQuit);
+        assert!(input.0.is_empty());
+        assert!(session.turns.is_empty());
+        assert!(server.next().await.is_none());
+    }
+
+    #[test]
+    fn current_symbol_follows_the_player_to_move() {
+        let mut session = Session::new(Player::Second, Ruleset::default());
+        assert_eq!(session.current_symbol(), Slot::X);
+        session.current_player = Player::Second;
+        assert_eq!(session.current_symbol(), Slot::O);
+
+        let swapped = Ruleset {
+            swap_symbols: true,
+            ..Ruleset::default()
+        };
+        let mut session = Session::new(Player::Second, swapped);
+        assert_eq!(session.current_symbol(), Slot::O);
+        session.current_player = Player::Second;
+        assert_eq!(session.current_symbol(), Slot::X);
+    }
+
+    #[test]
+    fn board_of_another_size_is_reported() {
+        let session = Session::new(Player::First, Ruleset::default());
+
+        assert!(session.check_dimensions(&Board::new(7, 7)).is_ok());
+        assert!(matches!(
+            session.check_dimensions(&Board::new(5, 6)),
+            Err(ClientError::BoardMismatch {
+                server: (5, 6),
+                client: (7, 7),
+            })
+        ));
+    }
+
+    #[test]
+    fn unplayable_turn_leaves_the_session_as_it_was() {
+        let mut session = Session::new(Player::First, Ruleset::default());
+        let off_the_board = Turn {
+            source: Player::First,
+            mov: Move {
+                side: Side::Left,
+                row: 9,
+            },
+        };
+
+        assert!(session.finish_turn(off_the_board).is_err());
+        assert!(session.turns.is_empty());
+        assert_eq!(session.current_player, Player::First);
+
+        let turn = Turn {
+            source: Player::First,
+            mov: Move {
+                side: Side::Left,
+                row: 0,
+            },
+        };
+        assert_eq!(session.finish_turn(turn).unwrap(), None);
+        assert_eq!(session.turns, vec![turn]);
+        assert_eq!(session.current_player, Player::Second);
+    }
+
+    #[tokio::test]
+    async fn resync_picks_up_the_history_of_turns() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = crate::Endpoint::Tcp(listener.local_addr().unwrap());
+        let turns = vec![
+            Turn {
+                source: Player::First,
+                mov: Move {
+                    side: Side::Left,
+                    row: 0,
+                },
+            },
+            Turn {
+                source: Player::Second,
+                mov: Move {
+                    side: Side::Right,
+                    row: 1,
+                },
+            },
+        ];
+        let snapshot = Response::BoardState {
+            board: Board::from_turns(7, 7, &turns).unwrap().encode(),
+            display: String::new(),
+            current_player: Player::First,
+            turns: turns.clone(),
+        };
+
+        let server = tokio::spawn(async move {
+            // the first connection drops, and the second one rejoins
+            drop(listener.accept().await.unwrap());
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut lines = Framed::new(stream, LinesCodec::new());
+            lines.next().await.unwrap().unwrap();
+            lines
+                .send(serde_json::to_string(&snapshot).unwrap())
+                .await
+                .unwrap();
+        });
+
+        let mut connection = Connection::connect_to(endpoint).await.unwrap();
+        connection.reconnect_attempts = 1;
+        connection.reconnect_token = Some(String::from("token"));
+        let mut session = Session::new(Player::First, Ruleset::default());
+
+        let lost = ClientError::ConnectionError(String::from("reset"));
+        session.resync(&mut connection, lost).await.unwrap();
+        server.await.unwrap();
+
+        assert_eq!(session.turns, turns);
+        assert_eq!(session.current_player, Player::First);
+        // the Turns from before the reconnect can be taken back
+        assert!(session.undo(2).unwrap());
+        assert_eq!(session.board.count_pieces(), 0);
+    }
 }
@@ -0,0 +1,101 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder, LinesCodec, LinesCodecError};
+
+/// A file that every line sent to or received from the server is appended
+/// to, for debugging disagreements between client and server.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    file: Arc<Mutex<File>>,
+}
+
+impl Transcript {
+    /// Open the transcript at the given path, appending to it if it exists.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Transcript {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Append a line to the transcript, prefixed with when it was sent, the
+    /// connection it went over, and which way it went: `<-` for a line
+    /// received and `->` for a line sent.
+    fn record(&self, connection: &str, direction: &str, line: &str) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+
+        // a panic elsewhere while writing leaves nothing worth protecting
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{} {} {} {}", timestamp, connection, direction, line)
+    }
+}
+
+/// Frames a stream as lines, like `LinesCodec`, while writing every line
+/// to a Transcript if there is one.
+#[derive(Debug)]
+pub struct TranscriptCodec {
+    lines: LinesCodec,
+    transcript: Option<Transcript>,
+    connection: String,
+}
+
+impl TranscriptCodec {
+    /// Frame the given connection, named in the Transcript as given.
+    pub fn new(transcript: Option<Transcript>, connection: impl Into<String>) -> Self {
+        TranscriptCodec {
+            lines: LinesCodec::new(),
+            transcript,
+            connection: connection.into(),
+        }
+    }
+
+    /// Start or stop writing lines to a Transcript.
+    pub fn set_transcript(&mut self, transcript: Option<Transcript>) {
+        self.transcript = transcript;
+    }
+
+    fn record(&self, direction: &str, line: Option<&str>) -> Result<(), LinesCodecError> {
+        match (&self.transcript, line) {
+            (Some(transcript), Some(line)) => {
+                Ok(transcript.record(&self.connection, direction, line)?)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Decoder for TranscriptCodec {
+    type Item = String;
+    type Error = LinesCodecError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<String>, LinesCodecError> {
+        let line = self.lines.decode(buf)?;
+        self.record("<-", line.as_deref())?;
+
+        Ok(line)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<String>, LinesCodecError> {
+        let line = self.lines.decode_eof(buf)?;
+        self.record("<-", line.as_deref())?;
+
+        Ok(line)
+    }
+}
+
+impl<T: AsRef<str>> Encoder<T> for TranscriptCodec {
+    type Error = LinesCodecError;
+
+    fn encode(&mut self, line: T, buf: &mut BytesMut) -> Result<(), LinesCodecError> {
+        self.record("->", Some(line.as_ref()))?;
+        self.lines.encode(line, buf)
+    }
+}
@@ -0,0 +1,51 @@
+use std::env;
+use std::fs;
+
+use structopt::StructOpt;
+
+use client::error::ClientError;
+use client::session::Session;
+use client::{Client, Player, Ruleset};
+
+#[test]
+fn board_file_sets_up_the_session() {
+    let path = env::temp_dir().join(format!("sidestacker-board-{}", std::process::id()));
+    fs::write(&path, "X___/_O__/____/X___\n").unwrap();
+    let mut session = Session::new(Player::First, Ruleset::default());
+
+    session.start_from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(session.board.encode(), "X___/_O__/____/X___");
+    assert_eq!((session.ruleset.height, session.ruleset.width), (4, 4));
+    // the first Player has placed one more piece than the second
+    assert_eq!(session.current_player, Player::Second);
+    assert!(session.turns.is_empty());
+}
+
+#[test]
+fn malformed_board_file_is_an_error() {
+    let path = env::temp_dir().join(format!("sidestacker-bad-board-{}", std::process::id()));
+    fs::write(&path, "X__/O?_").unwrap();
+    let mut session = Session::new(Player::First, Ruleset::default());
+
+    let loaded = session.start_from_file(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert!(matches!(loaded, Err(ClientError::MalformedBoard)));
+}
+
+#[test]
+fn board_file_is_only_for_offline_games() {
+    let online = ["client", "connect", "--board-from-file", "board.txt"];
+    let offline = [
+        "client",
+        "connect",
+        "--offline",
+        "--board-from-file",
+        "board.txt",
+    ];
+
+    assert!(Client::from_iter_safe(&online).is_err());
+    assert!(Client::from_iter_safe(&offline).is_ok());
+}
@@ -0,0 +1,541 @@
+use std::net::{SocketAddr, TcpListener};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::sink::SinkExt;
+use structopt::StructOpt;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::{sleep, timeout};
+
+use client::ai::RandomStrategy;
+use client::error::ClientError;
+use client::input::Scripted;
+use client::session::{Outcome, Session};
+use client::transcript::Transcript;
+use client::{
+    process, Connection, Endpoint, GameResult, Move, Player, Request, Response, Side, Turn,
+};
+
+/// Where the server keeps its database, relative to the crate being tested.
+const DB_DIR: &str = "../db";
+
+/// Find a port on localhost that nothing is listening on.
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("no free port on localhost")
+}
+
+/// Count the games saved in the server's database, along with the result
+/// of the most recent one.
+fn saved_games() -> (i64, Option<String>) {
+    let db = server::init_db().expect("couldn't open the database");
+
+    let count = db
+        .query_row("SELECT COUNT(*) FROM games", [], |row| row.get(0))
+        .unwrap();
+    let result = db
+        .query_row(
+            "SELECT result FROM games ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+
+    (count, result)
+}
+
+/// Connect to the server, join the game, and play the given moves.
+async fn play(addr: SocketAddr, moves: &[&str]) -> GameResult {
+    let mut connection = loop {
+        match Connection::connect(addr).await {
+            Ok(connection) => break connection,
+            // the server may not be listening yet
+            Err(_) => sleep(Duration::from_millis(20)).await,
+        }
+    };
+
+    connection
+        .lines
+        .send(serde_json::to_string(&Request::Join).unwrap())
+        .await
+        .unwrap();
+
+    let mut session = match connection.next_response().await.unwrap() {
+        Some(Response::Welcome {
+            player,
+            ruleset,
+            best_of,
+            ..
+        }) => {
+            let mut session = Session::new(player, ruleset);
+            session.best_of = best_of;
+            session
+        }
+        response => panic!("expected a Welcome, got {:?}", response),
+    };
+
+    let mut source = Scripted(moves.iter().map(|mov| mov.to_string()).collect());
+    process(&mut session, &mut connection, &mut source)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn two_clients_play_a_game_to_the_end() {
+    std::fs::create_dir_all(DB_DIR).unwrap();
+    let (games_before, _) = saved_games();
+
+    let addr = free_addr();
+    let params = server::Params::from_iter(&["server", "-a", &addr.to_string(), "--quiet"]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    // the first Player fills a column from the left while the second Player
+    // stacks up on the right, so the first Player's fourth piece wins
+    let first = play(addr, &["0L", "1L", "2L", "3L"]);
+    let second = async {
+        // join second, so the seats are handed out in a known order
+        sleep(Duration::from_millis(100)).await;
+        play(addr, &["0R", "1R", "2R"]).await
+    };
+    let (first, second) = tokio::join!(first, second);
+
+    assert_eq!(first, GameResult::Win(Player::First));
+    assert_eq!(second, GameResult::Win(Player::First));
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+
+    // the game is saved once the server lets go of it
+    for _ in 0..50 {
+        if saved_games().0 > games_before {
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    let (games_after, result) = saved_games();
+    assert_eq!(games_after, games_before + 1);
+    assert_eq!(result.as_deref(), Some(r#"{"Win":"First"}"#));
+}
+
+#[tokio::test]
+async fn match_goes_on_until_a_player_wins_two_games() {
+    let addr = free_addr();
+    let params = server::Params::from_iter(&[
+        "server",
+        "-a",
+        &addr.to_string(),
+        "--no-persist",
+        "--quiet",
+        "--best-of",
+        "3",
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    // the first Player fills a column from the left in both games, and the
+    // second Player, who opens the second game, never lines up four
+    let first = play(addr, &["0L", "1L", "2L", "3L", "0L", "1L", "2L", "3L"]);
+    let second = async {
+        sleep(Duration::from_millis(100)).await;
+        play(addr, &["0R", "1R", "2R", "0R", "1R", "2R", "4R"]).await
+    };
+    let (first, second) = timeout(Duration::from_secs(10), async {
+        tokio::join!(first, second)
+    })
+    .await
+    .expect("the match never ended");
+
+    assert_eq!(first, GameResult::Win(Player::First));
+    assert_eq!(second, GameResult::Win(Player::First));
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn player_arriving_mid_game_is_told_once() {
+    let addr = free_addr();
+    let params =
+        server::Params::from_iter(&["server", "-a", &addr.to_string(), "--no-persist", "--quiet"]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    let mut seated = Vec::new();
+    for _ in 0..2 {
+        let mut connection = loop {
+            match Connection::connect(addr).await {
+                Ok(connection) => break connection,
+                Err(_) => sleep(Duration::from_millis(20)).await,
+            }
+        };
+        connection
+            .lines
+            .send(serde_json::to_string(&Request::Join).unwrap())
+            .await
+            .unwrap();
+        connection.next_response().await.unwrap();
+        seated.push(connection);
+    }
+
+    // the client remembers the server it connects to, so it's given a
+    // config directory of its own
+    let config_dir = std::env::temp_dir().join(format!("sidestacker-late-{}", std::process::id()));
+    let late = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args(["connect", "-a", &addr.to_string()])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .output();
+    let late = timeout(Duration::from_secs(10), late)
+        .await
+        .expect("the client didn't give up")
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&config_dir);
+
+    assert!(!late.status.success());
+    let stderr = String::from_utf8(late.stderr).unwrap();
+    let message = ClientError::GameInProgress.to_string();
+    assert_eq!(stderr.matches(&message).count(), 1, "{}", stderr);
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn player_is_welcomed_over_a_unix_socket() {
+    let dir = std::env::temp_dir().join(format!("sidestacker-unix-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("server.sock");
+    let params = server::Params::from_iter(&[
+        "server",
+        "--unix-socket",
+        path.to_str().unwrap(),
+        "--no-persist",
+        "--quiet",
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    let mut connection = loop {
+        match Connection::connect_to(Endpoint::Unix(path.clone())).await {
+            Ok(connection) => break connection,
+            // the socket doesn't exist until the server is listening
+            Err(_) => sleep(Duration::from_millis(20)).await,
+        }
+    };
+    connection
+        .lines
+        .send(serde_json::to_string(&Request::Join).unwrap())
+        .await
+        .unwrap();
+    let welcome = connection.next_response().await.unwrap();
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    match welcome {
+        Some(Response::Welcome { player, .. }) => assert_eq!(player, Player::First),
+        response => panic!("expected a Welcome, got {:?}", response),
+    }
+}
+
+#[tokio::test]
+async fn client_keeps_trying_until_the_server_is_up() {
+    let addr = free_addr();
+    let config_dir = std::env::temp_dir().join(format!("sidestacker-retry-{}", std::process::id()));
+    let mut client = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args([
+            "connect",
+            "-a",
+            &addr.to_string(),
+            "--connect-retries",
+            "50",
+            "--retry-delay-ms",
+            "50",
+            "--self-play",
+        ])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    // the first Player fills a column from the left while the second
+    // Player stacks up on the right
+    let mut stdin = client.stdin.take().unwrap();
+    stdin
+        .write_all(b"0L\n0R\n1L\n1R\n2L\n2R\n3L\n")
+        .await
+        .unwrap();
+    drop(stdin);
+
+    // the server only starts once the client has had to retry
+    sleep(Duration::from_millis(300)).await;
+    let params = server::Params::from_iter(&[
+        "server",
+        "-a",
+        &addr.to_string(),
+        "--no-persist",
+        "--quiet",
+        "--allow-self-play",
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    let output = timeout(Duration::from_secs(10), client.wait_with_output())
+        .await
+        .expect("the client never finished")
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&config_dir);
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("retrying"), "{}", stderr);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Game won by First Player!"), "{}", stdout);
+}
+
+/// Join the game over an in-memory stream and let the computer play every
+/// move.
+async fn bot(state: &Arc<Mutex<server::Shared>>, id: server::ClientId) -> GameResult {
+    let (client, stream) = tokio::io::duplex(4096);
+    tokio::spawn(server::process(Arc::clone(state), stream, id));
+    let mut connection = Connection::from_stream(client);
+
+    connection
+        .lines
+        .send(serde_json::to_string(&Request::Join).unwrap())
+        .await
+        .unwrap();
+    let mut session = match connection.next_response().await.unwrap() {
+        Some(Response::Welcome {
+            player, ruleset, ..
+        }) => Session::new(player, ruleset),
+        response => panic!("expected a Welcome, got {:?}", response),
+    };
+    session.auto_move = Some(Box::new(RandomStrategy));
+
+    process(&mut session, &mut connection, &mut Scripted::default())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn two_bots_play_a_game_to_the_end() {
+    let params = server::Params::from_iter(&["server", "--no-persist", "--quiet"]);
+    let state = Arc::new(Mutex::new(server::Shared::try_new(&params).unwrap()));
+
+    let (first, second) = timeout(Duration::from_secs(10), async {
+        tokio::join!(bot(&state, 1), bot(&state, 2))
+    })
+    .await
+    .expect("the bots never finished");
+
+    assert_eq!(first, second);
+    assert!(matches!(first, GameResult::Win(_) | GameResult::Tie));
+    assert!(state.lock().await.result.is_some());
+}
+
+#[tokio::test]
+async fn transcripts_record_the_welcome_and_the_turns() {
+    let dir = std::env::temp_dir().join(format!("sidestacker-transcript-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (client_path, server_path) = (dir.join("client.log"), dir.join("server.log"));
+
+    let addr = free_addr();
+    let params = server::Params::from_iter(&[
+        "server",
+        "-a",
+        &addr.to_string(),
+        "--no-persist",
+        "--quiet",
+        "--transcript",
+        server_path.to_str().unwrap(),
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    let mut seated = Vec::new();
+    for _ in 0..2 {
+        let mut connection = loop {
+            match Connection::connect(addr).await {
+                Ok(connection) => break connection,
+                Err(_) => sleep(Duration::from_millis(20)).await,
+            }
+        };
+        if seated.is_empty() {
+            connection.record_to(Transcript::open(&client_path).unwrap());
+        }
+        connection
+            .lines
+            .send(serde_json::to_string(&Request::Join).unwrap())
+            .await
+            .unwrap();
+        connection.next_response().await.unwrap();
+        seated.push(connection);
+    }
+
+    let first = &mut seated[0];
+    let turn = Turn {
+        source: Player::First,
+        mov: Move {
+            side: Side::Left,
+            row: 0,
+        },
+    };
+    first
+        .lines
+        .send(serde_json::to_string(&Request::Turn(turn)).unwrap())
+        .await
+        .unwrap();
+    loop {
+        if let Some(Response::Acknowledged { .. }) = first.next_response().await.unwrap() {
+            break;
+        }
+    }
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    let client_log = std::fs::read_to_string(&client_path).unwrap();
+    let server_log = std::fs::read_to_string(&server_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // each side writes what it received with `<-` and what it sent with `->`
+    let recorded = |log: &str, direction: &str, message: &str| {
+        log.lines()
+            .any(|line| line.contains(direction) && line.contains(message))
+    };
+    assert!(recorded(&client_log, "<-", "Welcome"), "{}", client_log);
+    assert!(recorded(&client_log, "->", "Turn"), "{}", client_log);
+    assert!(recorded(&server_log, "->", "Welcome"), "{}", server_log);
+    assert!(recorded(&server_log, "<-", "Turn"), "{}", server_log);
+}
+
+#[tokio::test]
+async fn game_can_be_driven_one_step_at_a_time() {
+    let params =
+        server::Params::from_iter(&["server", "--no-persist", "--quiet", "--allow-self-play"]);
+    let state = Arc::new(Mutex::new(server::Shared::try_new(&params).unwrap()));
+    let (client, stream) = tokio::io::duplex(4096);
+    tokio::spawn(server::process(Arc::clone(&state), stream, 1));
+    let mut connection = Connection::from_stream(client);
+
+    connection
+        .lines
+        .send(serde_json::to_string(&Request::SelfPlay).unwrap())
+        .await
+        .unwrap();
+    let mut session = match connection.next_response().await.unwrap() {
+        Some(Response::Welcome {
+            player, ruleset, ..
+        }) => Session::new(player, ruleset),
+        response => panic!("expected a Welcome, got {:?}", response),
+    };
+    session.self_play = true;
+    let mut source = Scripted::default();
+
+    // a Move off the Board is turned away without touching the game
+    let off_the_board = Move {
+        side: Side::Left,
+        row: 9,
+    };
+    let step = session
+        .step(&mut connection, off_the_board, &mut source)
+        .await
+        .unwrap();
+    assert_eq!(step.outcome, Outcome::Continue);
+    assert!(!step.board_changed);
+
+    // the first Player fills a column from the left while the second
+    // Player stacks up on the right, so the first Player's fourth piece wins
+    let moves = [
+        (0, Side::Left),
+        (0, Side::Right),
+        (1, Side::Left),
+        (1, Side::Right),
+        (2, Side::Left),
+        (2, Side::Right),
+    ];
+    for (row, side) in moves {
+        let step = session
+            .step(&mut connection, Move { side, row }, &mut source)
+            .await
+            .unwrap();
+        assert_eq!(step.outcome, Outcome::Continue);
+        assert!(step.board_changed);
+    }
+    assert_eq!(session.current_player, Player::First);
+
+    let winning = Move {
+        side: Side::Left,
+        row: 3,
+    };
+    let step = session
+        .step(&mut connection, winning, &mut source)
+        .await
+        .unwrap();
+    assert_eq!(step.outcome, Outcome::Over(GameResult::Win(Player::First)));
+    assert_eq!(session.turns.len(), 7);
+}
+
+#[tokio::test]
+async fn server_name_is_shown_when_connecting() {
+    let addr = free_addr();
+    let params = server::Params::from_iter(&[
+        "server",
+        "-a",
+        &addr.to_string(),
+        "--no-persist",
+        "--quiet",
+        "--server-name",
+        "The Back Room",
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(server::run_server(params, on_shutdown));
+
+    let config_dir = std::env::temp_dir().join(format!("sidestacker-name-{}", std::process::id()));
+    let mut client = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args([
+            "connect",
+            "-a",
+            &addr.to_string(),
+            "--connect-retries",
+            "50",
+        ])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(client.stdout.take().unwrap()).lines();
+
+    // the client goes on to wait for an opponent, so it's stopped once
+    // it's shown the banner
+    let banner = timeout(Duration::from_secs(10), async {
+        while let Some(line) = stdout.next_line().await.unwrap() {
+            if line.starts_with("Connected to ") {
+                return line;
+            }
+        }
+        panic!("the client never connected");
+    })
+    .await
+    .expect("timed out waiting for the banner");
+    client.kill().await.unwrap();
+    let _ = std::fs::remove_dir_all(&config_dir);
+
+    assert_eq!(banner, "Connected to The Back Room");
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,32 @@
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+
+use client::history;
+
+// the history is found through the environment, which every test in this
+// file would share, so everything is checked in a single test
+#[test]
+fn recorded_servers_are_recalled_most_recent_first() {
+    let config_dir = env::temp_dir().join(format!("sidestacker-history-{}", std::process::id()));
+    env::set_var("XDG_CONFIG_HOME", &config_dir);
+    let first: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    let second: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+
+    // nothing has been recorded yet
+    assert_eq!(
+        history::history_path(),
+        Some(config_dir.join("sidestacker").join("history"))
+    );
+    assert_eq!(history::last(), None);
+
+    history::record(first).unwrap();
+    assert_eq!(history::last(), Some(first));
+
+    history::record(second).unwrap();
+    history::record(first).unwrap();
+    assert_eq!(history::last(), Some(first));
+    assert_eq!(history::recent(), vec![first, second]);
+
+    fs::remove_dir_all(&config_dir).unwrap();
+}
@@ -0,0 +1,205 @@
+use std::convert::TryFrom;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use client::rules::Lang;
+use client::Move;
+
+#[tokio::test]
+async fn offline_game_against_the_computer_finishes() {
+    let mut client = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args(["connect", "--offline"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+
+    // enough moves into every Row to fill the Board, so the game ends
+    // however the computer plays; moves into a full Row are just retried
+    let script = (0..7)
+        .flat_map(|row| std::iter::repeat_n(format!("{}L\n", row), 7))
+        .collect::<String>();
+    let mut stdin = client.stdin.take().unwrap();
+    stdin.write_all(script.as_bytes()).await.unwrap();
+    drop(stdin);
+
+    let output = timeout(Duration::from_secs(10), client.wait_with_output())
+        .await
+        .expect("the game never finished")
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Game won by") || stdout.contains("Game ended in a tie!"),
+        "{}",
+        stdout
+    );
+}
+
+/// Show the Board of an offline game with the given environment, then
+/// quit, returning everything the client printed.
+async fn board_shown_with(env: &[(&str, &str)]) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_client"));
+    command
+        .args(["connect", "--offline"])
+        .env_remove("NO_COLOR")
+        .env_remove("LC_ALL")
+        .env_remove("LC_CTYPE")
+        .env_remove("LANG")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true);
+    let mut client = command.spawn().unwrap();
+
+    let mut stdin = client.stdin.take().unwrap();
+    stdin.write_all(b"quit\n").await.unwrap();
+    drop(stdin);
+
+    let output = timeout(Duration::from_secs(10), client.wait_with_output())
+        .await
+        .expect("the client never quit")
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[tokio::test]
+async fn board_is_drawn_plainly_unless_the_terminal_can_take_more() {
+    assert!(board_shown_with(&[("LANG", "en_US.UTF-8")])
+        .await
+        .contains('│'));
+
+    let plain = [
+        board_shown_with(&[("LANG", "en_US.UTF-8"), ("NO_COLOR", "1")]).await,
+        board_shown_with(&[("LANG", "C")]).await,
+        board_shown_with(&[("LANG", "en_US.UTF-8"), ("LC_ALL", "C")]).await,
+    ];
+    for shown in &plain {
+        assert!(!shown.contains('│'), "{}", shown);
+        assert!(shown.contains("0 ["), "{}", shown);
+    }
+}
+
+#[tokio::test]
+async fn echoed_moves_can_be_typed_back_in() {
+    let mut client = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args(["connect", "--offline", "--echo-moves"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+
+    // too few moves for anyone to win
+    let mut stdin = client.stdin.take().unwrap();
+    stdin.write_all(b"0L\n1L\n12R\n2L\nquit\n").await.unwrap();
+    drop(stdin);
+
+    let output = timeout(Duration::from_secs(10), client.wait_with_output())
+        .await
+        .expect("the client never quit")
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    let echoed = stderr
+        .lines()
+        .map(|line| {
+            let (player, command) = line.split_once(' ').unwrap();
+            let mov = Move::try_from(command.to_string()).unwrap();
+            assert_eq!(mov.to_command(), command);
+            (player.to_string(), mov)
+        })
+        .collect::<Vec<_>>();
+
+    // the move into a Row that isn't on the Board is never played
+    let typed = ["0L", "1L", "2L"]
+        .iter()
+        .map(|command| Move::try_from(command.to_string()).unwrap())
+        .collect::<Vec<_>>();
+    let played = echoed
+        .iter()
+        .filter(|(player, _)| player == "First")
+        .map(|(_, mov)| *mov)
+        .collect::<Vec<_>>();
+    assert_eq!(played, typed);
+    // the computer's replies are echoed too
+    assert_eq!(echoed.len(), 6, "{}", stderr);
+}
+
+#[tokio::test]
+async fn rules_text_file_replaces_the_standard_rules() {
+    let path = std::env::temp_dir().join(format!("sidestacker-rules-{}.txt", std::process::id()));
+    std::fs::write(&path, "House rules: the loser buys lunch.").unwrap();
+
+    let mut client = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args([
+            "connect",
+            "--offline",
+            "--rules-text",
+            path.to_str().unwrap(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut stdin = client.stdin.take().unwrap();
+    stdin.write_all(b"quit\n").await.unwrap();
+    drop(stdin);
+
+    let output = timeout(Duration::from_secs(10), client.wait_with_output())
+        .await
+        .expect("the client never quit")
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("House rules: the loser buys lunch."),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains(Lang::default().rules_text()), "{}", stdout);
+}
+
+#[tokio::test]
+async fn progress_line_counts_the_pieces_played() {
+    let mut client = Command::new(env!("CARGO_BIN_EXE_client"))
+        .args(["connect", "--offline"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut stdin = client.stdin.take().unwrap();
+    stdin.write_all(b"0L\n1L\nquit\n").await.unwrap();
+    drop(stdin);
+
+    let output = timeout(Duration::from_secs(10), client.wait_with_output())
+        .await
+        .expect("the client never quit")
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    // the Player is asked for each Move after the computer has replied
+    let progress = stdout
+        .lines()
+        .filter(|line| line.ends_with("cells filled"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        progress,
+        [
+            "0 of 49 cells filled",
+            "2 of 49 cells filled",
+            "4 of 49 cells filled"
+        ]
+    );
+}
@@ -0,0 +1,208 @@
+use std::convert::TryFrom;
+
+use futures::{SinkExt, StreamExt};
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use client::error::ClientError;
+use client::fixtures::{sample_ack, sample_turn, sample_welcome};
+use client::input::Scripted;
+use client::session::Session;
+use client::{
+    check_protocol_version, process, Connection, GameResult, Move, Player, Request, Response,
+    Ruleset, Side, Turn, PROTOCOL_MINOR_VERSION, PROTOCOL_VERSION,
+};
+
+/// Serialize a message and parse it back, as it would be sent over the wire.
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(message: &T) -> T {
+    let line = serde_json::to_string(message).unwrap();
+    serde_json::from_str(&line).unwrap()
+}
+
+#[test]
+fn turns_survive_a_round_trip() {
+    let turn = sample_turn(Player::First, 3, Side::Right);
+
+    assert_eq!(round_trip(&turn), turn);
+    assert_eq!(
+        round_trip(&Request::Turn(turn)),
+        Request::Turn(sample_turn(Player::First, 3, Side::Right))
+    );
+}
+
+#[test]
+fn responses_survive_a_round_trip() {
+    let responses = vec![
+        sample_welcome(Player::Second),
+        sample_ack((3, 0), Player::Second),
+        Response::Turn(sample_turn(Player::Second, 0, Side::Left)),
+    ];
+
+    for response in &responses {
+        assert_eq!(&round_trip(response), response);
+    }
+}
+
+#[test]
+fn turns_are_sent_in_the_expected_shape() {
+    let turn: Turn =
+        serde_json::from_str(r#"{"source":"First","mov":{"side":"Right","row":3}}"#).unwrap();
+
+    assert_eq!(turn, sample_turn(Player::First, 3, Side::Right));
+}
+
+// the server is built without the client's features
+#[cfg(not(feature = "ascii-only"))]
+#[test]
+fn both_crates_show_the_same_game_full_message() {
+    assert_eq!(
+        client::error::GAME_FULL_MESSAGE,
+        server::error::GAME_FULL_MESSAGE
+    );
+}
+
+#[cfg(feature = "ascii-only")]
+#[test]
+fn ascii_only_game_full_message_is_plain_ascii() {
+    assert!(client::error::GAME_FULL_MESSAGE.is_ascii());
+}
+
+#[test]
+fn matching_major_version_proceeds() {
+    assert!(check_protocol_version(PROTOCOL_VERSION).is_ok());
+
+    // a server on another minor version still speaks the same major one
+    let welcome: Response = serde_json::from_value(serde_json::json!({
+        "Welcome": {
+            "player": "First",
+            "ruleset": Ruleset::default(),
+            "protocol_version": PROTOCOL_VERSION,
+            "protocol_minor_version": PROTOCOL_MINOR_VERSION + 1,
+        }
+    }))
+    .unwrap();
+    match welcome {
+        Response::Welcome {
+            protocol_version, ..
+        } => assert!(check_protocol_version(protocol_version).is_ok()),
+        response => panic!("expected a Welcome, got {:?}", response),
+    }
+}
+
+#[test]
+fn mismatched_major_version_errors() {
+    assert!(matches!(
+        check_protocol_version(PROTOCOL_VERSION + 1),
+        Err(ClientError::ProtocolMismatch { server, client })
+            if server == PROTOCOL_VERSION + 1 && client == PROTOCOL_VERSION
+    ));
+    // servers that predate versioning count as version 0
+    assert!(check_protocol_version(0).is_err());
+}
+
+#[tokio::test]
+async fn unknown_responses_are_skipped() {
+    let (client, mut server) = tokio::io::duplex(1024);
+    let mut connection = Connection::from_stream(client);
+
+    // a Response from a newer server, then one this client knows
+    let known = serde_json::to_string(&Response::GameStart).unwrap();
+    let lines = format!("{{\"Fireworks\":{{\"colour\":\"red\"}}}}\n{}\n", known);
+    server.write_all(lines.as_bytes()).await.unwrap();
+    drop(server);
+
+    assert_eq!(
+        connection.next_response().await.unwrap(),
+        Some(Response::GameStart)
+    );
+    assert_eq!(connection.next_response().await.unwrap(), None);
+}
+
+#[test]
+fn moves_written_as_commands_parse_back() {
+    for row in [0, 7, 10, 123] {
+        for side in [Side::Left, Side::Right] {
+            let mov = Move { side, row };
+            let command = mov.to_command();
+
+            assert_eq!(Move::try_from(command.clone()).unwrap(), mov, "{}", command);
+        }
+    }
+    assert_eq!(
+        Move {
+            side: Side::Right,
+            row: 12
+        }
+        .to_command(),
+        "12R"
+    );
+}
+
+#[test]
+fn players_round_trip_through_their_index() {
+    for player in [Player::First, Player::Second] {
+        assert_eq!(Player::from_index(player.index()), Some(player));
+    }
+    assert_eq!(Player::First.index(), 0);
+    assert_eq!(Player::from_index(2), None);
+}
+
+#[test]
+fn each_mistyped_move_gets_its_own_hint() {
+    let hint = |command: &str| {
+        Move::try_from(command.to_string())
+            .unwrap_err()
+            .retry_hint()
+    };
+
+    assert_eq!(hint("L"), Some("Moves look like `2R` or `5l`. Try again."));
+    assert_eq!(hint("3X"), Some("The side must be `l` or `r`. Try again."));
+    assert_eq!(hint("xL"), Some("There's no such row. Try again."));
+    assert_eq!(
+        ClientError::FullRow.retry_hint(),
+        Some("That row is full. Try another.")
+    );
+    // anything else is shown in full
+    assert_eq!(ClientError::GameFull.retry_hint(), None);
+}
+
+#[tokio::test]
+async fn game_is_played_over_a_wrapped_stream() {
+    let (client, server) = tokio::io::duplex(1024);
+    let mut connection = Connection::from_stream(client);
+
+    let server = tokio::spawn(async move {
+        let mut lines = Framed::new(server, LinesCodec::new());
+        let send = |response: Response| serde_json::to_string(&response).unwrap();
+
+        lines.send(send(Response::GameStart)).await.unwrap();
+        let line = lines.next().await.unwrap().unwrap();
+        let turn = match serde_json::from_str(&line).unwrap() {
+            Request::Turn(turn) => turn,
+            request => panic!("expected a Turn, got {:?}", request),
+        };
+        lines
+            .send(send(Response::Acknowledged {
+                coordinate: (0, 6),
+                game_over: Some(Some(Player::First)),
+                next_player: Player::First,
+                board: None,
+            }))
+            .await
+            .unwrap();
+
+        turn
+    });
+
+    let mut session = Session::new(Player::First, Ruleset::default());
+    let mut input = Scripted(vec![String::from("0L")]);
+    let result = process(&mut session, &mut connection, &mut input)
+        .await
+        .unwrap();
+
+    assert_eq!(result, GameResult::Win(Player::First));
+    assert_eq!(
+        server.await.unwrap(),
+        sample_turn(Player::First, 0, Side::Left)
+    );
+}
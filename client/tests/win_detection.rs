@@ -0,0 +1,311 @@
+use proptest::prelude::*;
+
+use client::game::{board::Board, tracker::WinTracker, Direction, Slot};
+use client::Side;
+
+/// The number of consecutive Slots needed to win.
+const WIN_LENGTH: usize = 4;
+
+/// Board dimensions, kept small so failing cases shrink to readable boards.
+fn dimensions() -> impl Strategy<Value = (usize, usize)> {
+    (WIN_LENGTH..=8, WIN_LENGTH..=8)
+}
+
+/// A Board's dimensions along with a sequence of (row, side) insertions.
+fn game() -> impl Strategy<Value = ((usize, usize), Vec<(usize, Side)>)> {
+    dimensions().prop_flat_map(|(height, width)| {
+        let side = prop_oneof![Just(Side::Left), Just(Side::Right)];
+        let moves = prop::collection::vec((0..height, side), 0..height * width);
+
+        (Just((height, width)), moves)
+    })
+}
+
+/// One of the Directions a winning line can run in, counting each line
+/// from the end it starts at.
+fn direction() -> impl Strategy<Value = Direction> {
+    prop_oneof![
+        Just(Direction::East),
+        Just(Direction::South),
+        Just(Direction::SouthEast),
+        Just(Direction::NorthEast),
+    ]
+}
+
+/// A Board's dimensions, a Direction, and an origin from which a run of
+/// `WIN_LENGTH` Slots in that Direction stays on the Board.
+fn run() -> impl Strategy<Value = ((usize, usize), Direction, (usize, usize))> {
+    (dimensions(), direction()).prop_flat_map(|((height, width), direction)| {
+        let span = WIN_LENGTH - 1;
+        let (d_row, d_col) = direction.offset();
+
+        let rows = match d_row {
+            1 => 0..height - span,
+            -1 => span..height,
+            _ => 0..height,
+        };
+        let cols = match d_col {
+            1 => 0..width - span,
+            _ => 0..width,
+        };
+
+        (Just((height, width)), Just(direction), (rows, cols))
+    })
+}
+
+/// The coordinates of `length` Slots in a line from `origin` in the given
+/// Direction.
+fn line(origin: (usize, usize), direction: Direction, length: usize) -> Vec<(usize, usize)> {
+    let (d_row, d_col) = direction.offset();
+
+    (0..length as isize)
+        .map(|step| {
+            (
+                (origin.0 as isize + d_row * step) as usize,
+                (origin.1 as isize + d_col * step) as usize,
+            )
+        })
+        .collect()
+}
+
+fn slot_at(board: &Board, row: usize, col: usize) -> Slot {
+    *board.get(row, col).unwrap()
+}
+
+/// Counts the run of `slot` through (`row`, `col`) along (`d_row`, `d_col`)
+/// by walking the Board cell by cell in both directions.
+fn run_length(board: &Board, slot: Slot, row: usize, col: usize, d_row: isize, d_col: isize) -> usize {
+    let mut length = 1;
+
+    for sign in [-1, 1].iter() {
+        let (mut r, mut c) = (row as isize, col as isize);
+
+        loop {
+            r += d_row * sign;
+            c += d_col * sign;
+
+            if r < 0 || c < 0 || r >= board.height as isize || c >= board.width as isize {
+                break;
+            }
+
+            if slot_at(board, r as usize, c as usize) != slot {
+                break;
+            }
+
+            length += 1;
+        }
+    }
+
+    length
+}
+
+/// Whether placing `slot` at (`row`, `col`) completed a winning line.
+fn is_win(board: &Board, slot: Slot, row: usize, col: usize) -> bool {
+    [(1, 0), (0, 1), (1, 1), (1, -1)]
+        .iter()
+        .any(|&(d_row, d_col)| run_length(board, slot, row, col, d_row, d_col) >= WIN_LENGTH)
+}
+
+/// A Board's dimensions along with what fills each of its Slots, in rows
+/// from the top, so runs can be longer than needed to win and can cross
+/// Blocked Slots.
+fn filled_board() -> impl Strategy<Value = ((usize, usize), Vec<Slot>)> {
+    dimensions().prop_flat_map(|(height, width)| {
+        let slot = prop_oneof![
+            3 => Just(Slot::X),
+            3 => Just(Slot::O),
+            2 => Just(Slot::Blank),
+            1 => Just(Slot::Blocked),
+        ];
+
+        (
+            Just((height, width)),
+            prop::collection::vec(slot, height * width),
+        )
+    })
+}
+
+/// Win detection as it was before it stopped at the first winning run:
+/// every run through (`row`, `col`) is counted out to its full length in
+/// both directions along all four axes.
+fn full_scan_winner(board: &Board, slot: Slot, row: usize, col: usize) -> Option<Slot> {
+    let axes = [
+        (Direction::North, Direction::South),
+        (Direction::East, Direction::West),
+        (Direction::NorthEast, Direction::SouthWest),
+        (Direction::NorthWest, Direction::SouthEast),
+    ];
+    // every axis is counted, even after one has been found to win
+    let lengths = axes
+        .iter()
+        .map(|&(forward, backward)| {
+            let (d_row, d_col) = forward.offset();
+            debug_assert_eq!(backward.offset(), (-d_row, -d_col));
+
+            run_length(board, slot, row, col, d_row, d_col)
+        })
+        .collect::<Vec<_>>();
+
+    if lengths.iter().any(|&length| length >= WIN_LENGTH) {
+        Some(slot)
+    } else {
+        None
+    }
+}
+
+fn insert(board: &mut Board, row: usize, side: Side, slot: Slot) -> Option<(usize, usize)> {
+    match side {
+        Side::Left => board.insert_from_left(row, slot).ok(),
+        Side::Right => board.insert_from_right(row, slot).ok(),
+    }
+}
+
+proptest! {
+    /// Win detection agrees with a cell-by-cell scan after every move of a
+    /// random game, so both wins and non-wins are reported correctly.
+    #[test]
+    fn agrees_with_exhaustive_scan(((height, width), moves) in game()) {
+        let mut board = Board::new(height, width);
+        let mut slot = Slot::X;
+
+        for (row, side) in moves {
+            let (row, col) = match insert(&mut board, row, side, slot) {
+                Some(coordinate) => coordinate,
+                None => continue,
+            };
+
+            let expected = is_win(&board, slot, row, col);
+            let winner = board.is_game_over(row, col, &slot).unwrap();
+
+            prop_assert_eq!(winner.is_some(), expected);
+
+            if expected {
+                prop_assert_eq!(winner, Some(slot));
+                break;
+            }
+
+            slot = if slot == Slot::X { Slot::O } else { Slot::X };
+        }
+    }
+
+    /// A run of `WIN_LENGTH` Slots stacked into a single row is a win no
+    /// matter which side it was inserted from.
+    #[test]
+    fn horizontal_run_wins(
+        (height, width) in dimensions(),
+        row_seed in any::<prop::sample::Index>(),
+        from_left in any::<bool>(),
+    ) {
+        let row = row_seed.index(height);
+        let side = if from_left { Side::Left } else { Side::Right };
+        let mut board = Board::new(height, width);
+
+        let mut last = None;
+        for _ in 0..WIN_LENGTH {
+            last = insert(&mut board, row, side, Slot::O);
+        }
+
+        let (row, col) = last.unwrap();
+        prop_assert_eq!(board.is_game_over(row, col, &Slot::O).unwrap(), Some(Slot::O));
+    }
+
+    /// A run of `WIN_LENGTH` Slots in any Direction is a win, checked from
+    /// any Slot along it, not just its ends.
+    #[test]
+    fn run_in_any_direction_wins(
+        ((height, width), direction, origin) in run(),
+        pick in any::<prop::sample::Index>(),
+    ) {
+        let mut board = Board::new(height, width);
+        let cells = line(origin, direction, WIN_LENGTH);
+        for &(row, col) in &cells {
+            board.set_slot(row, col, Slot::X).unwrap();
+        }
+
+        let (row, col) = cells[pick.index(cells.len())];
+        prop_assert_eq!(board.is_game_over(row, col, &Slot::X).unwrap(), Some(Slot::X));
+    }
+
+    /// A run one Slot short of `WIN_LENGTH` in any Direction is not a win
+    /// from any Slot along it.
+    #[test]
+    fn short_run_in_any_direction_does_not_win(((height, width), direction, origin) in run()) {
+        let mut board = Board::new(height, width);
+        let cells = line(origin, direction, WIN_LENGTH - 1);
+        for &(row, col) in &cells {
+            board.set_slot(row, col, Slot::O).unwrap();
+        }
+
+        for &(row, col) in &cells {
+            prop_assert_eq!(board.is_game_over(row, col, &Slot::O).unwrap(), None);
+        }
+    }
+
+    /// One Slot short of a full run is never a win.
+    #[test]
+    fn short_run_does_not_win(
+        (height, width) in dimensions(),
+        row_seed in any::<prop::sample::Index>(),
+        from_left in any::<bool>(),
+    ) {
+        let row = row_seed.index(height);
+        let side = if from_left { Side::Left } else { Side::Right };
+        let mut board = Board::new(height, width);
+
+        let mut last = None;
+        for _ in 0..WIN_LENGTH - 1 {
+            last = insert(&mut board, row, side, Slot::X);
+        }
+
+        let (row, col) = last.unwrap();
+        prop_assert_eq!(board.is_game_over(row, col, &Slot::X).unwrap(), None);
+    }
+
+    /// The win detection that stops at the first winning run agrees with
+    /// the one that counted every run in full, for every piece on a random
+    /// Board.
+    #[test]
+    fn short_circuit_agrees_with_full_scan(((height, width), slots) in filled_board()) {
+        let mut board = Board::new(height, width);
+        for (index, slot) in slots.iter().enumerate() {
+            board.set_slot(index / width, index % width, *slot).unwrap();
+        }
+
+        for (index, slot) in slots.iter().enumerate() {
+            if let Slot::Blank | Slot::Blocked = slot {
+                continue;
+            }
+            let (row, col) = (index / width, index % width);
+
+            prop_assert_eq!(
+                board.is_game_over(row, col, slot).unwrap(),
+                full_scan_winner(&board, *slot, row, col)
+            );
+        }
+    }
+
+    /// A WinTracker fed the same moves agrees with `is_game_over` after
+    /// every move of a random game, even once the move is taken back and
+    /// played again.
+    #[test]
+    fn tracker_agrees_with_is_game_over(((height, width), moves) in game()) {
+        let mut board = Board::new(height, width);
+        let mut tracker = WinTracker::new(height, width, board.win_length);
+        let mut slot = Slot::X;
+
+        for (row, side) in moves {
+            let (row, col) = match insert(&mut board, row, side, slot) {
+                Some(coordinate) => coordinate,
+                None => continue,
+            };
+
+            let expected = board.is_game_over(row, col, &slot).unwrap().is_some();
+
+            prop_assert_eq!(tracker.place(row, col, slot), expected);
+            prop_assert_eq!(tracker.undo(), Some((row, col)));
+            prop_assert_eq!(tracker.place(row, col, slot), expected);
+
+            slot = if slot == Slot::X { Slot::O } else { Slot::X };
+        }
+    }
+}
@@ -1,34 +1,95 @@
-use std::sync::Arc;
+use std::path::PathBuf;
 
+use rusqlite::params;
 use structopt::StructOpt;
-use tokio::net::TcpListener;
-use tokio::sync::Mutex;
+use tokio::sync::oneshot;
 
-use server::{error::ServerError, process, Params, Server, Shared};
+use server::verify::{validate_turns, verify_games};
+use server::{error::ServerError, init_db, run_server, Params, Server};
 
 #[tokio::main]
 async fn main() -> Result<(), ServerError> {
-    let Server::Start(Params {
-        height,
-        width,
-        addr,
-    }) = Server::from_args();
+    let params = match Server::from_args() {
+        Server::Start(params) => params,
+        Server::Resume { id, mut params } => {
+            params.resume = Some(id);
+            params
+        }
+        Server::Verify { params } => return verify(&params),
+        Server::Import {
+            paths,
+            dry_run,
+            params,
+        } => return import(&paths, dry_run, &params),
+    };
 
-    let state = Arc::new(Mutex::new(Shared::try_new(height, width)?));
-    let listener = TcpListener::bind(&addr).await?;
+    // shut down cleanly on Ctrl-C
+    let (shutdown, on_shutdown) = oneshot::channel();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = shutdown.send(());
+        }
+    });
 
-    println!("Server running on {}", addr);
+    run_server(params, on_shutdown).await
+}
+
+/// Replay every saved game and report the ones that don't hold up, failing
+/// if there are any.
+fn verify(params: &Params) -> Result<(), ServerError> {
+    let (checked, problems) = verify_games(&init_db()?, &params.ruleset())?;
+
+    for problem in &problems {
+        println!("Game {}: {}", problem.id, problem.reason);
+    }
+    println!(
+        "Checked {} games, {} with problems.",
+        checked,
+        problems.len()
+    );
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ServerError::GamesFailedVerification {
+            count: problems.len(),
+        })
+    }
+}
 
-    loop {
-        let (stream, addr) = listener.accept().await?;
-        let state = Arc::clone(&state);
+/// Check each game file and save the ones that hold up, reporting on every
+/// file and failing if any don't. A dry run only checks them, without
+/// touching the database.
+fn import(paths: &[PathBuf], dry_run: bool, params: &Params) -> Result<(), ServerError> {
+    let ruleset = params.ruleset();
+    let connection = if dry_run { None } else { Some(init_db()?) };
 
-        tokio::spawn(async move {
-            println!("Got a connection");
+    let mut failed = 0;
+    for path in paths {
+        let checked = std::fs::read_to_string(path)
+            .map_err(|e| format!("it can't be read: {}", e))
+            .and_then(|turns| validate_turns(&ruleset, &turns).map(|_| turns));
 
-            if let Err(e) = process(state, stream, addr).await {
-                eprintln!("Error: {}", e);
+        match checked {
+            Ok(turns) => {
+                if let Some(connection) = &connection {
+                    connection.execute(
+                        "INSERT INTO games (turns, ruleset) VALUES (?1, ?2)",
+                        params![turns, serde_json::to_string(&ruleset)?],
+                    )?;
+                }
+                println!("{}: OK", path.display());
+            }
+            Err(reason) => {
+                failed += 1;
+                println!("{}: {}", path.display(), reason);
             }
-        });
+        }
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(ServerError::GamesFailedImport { count: failed })
     }
 }
@@ -5,16 +5,50 @@ use std::io;
 use serde_json::Error as JsonError;
 use tokio_util::codec;
 
+/// The message shown when a game can't accept any more players. Building
+/// with the `ascii-only` feature keeps it readable on terminals and log
+/// parsers that can't handle emoji.
+#[cfg(not(feature = "ascii-only"))]
+pub const GAME_FULL_MESSAGE: &str = "Game is at max capacity and can't accept any more players 😞";
+#[cfg(feature = "ascii-only")]
+pub const GAME_FULL_MESSAGE: &str = "Game is at max capacity and can't accept any more players :(";
+
 #[derive(Debug)]
 pub enum ServerError {
     /// Game is already full; can't connect more Players.
     GameFull,
+    /// The game already started; Players can't join it anymore.
+    GameInProgress,
+    /// A Turn attempted to insert into a full row.
+    FullRow,
+    /// A Turn attempted to insert into a non-existent row.
+    NonexistentRow,
+    /// A Turn attempted to insert into a row from a side it can't be
+    /// played from.
+    BlockedSide,
     /// An I/O occurred.
     IoError { source: io::Error },
     /// An error occurred while encoding or decoding a line.
     CodecError { source: codec::LinesCodecError },
     /// A Player attempted to take a turn when it isn't their turn.
     NotYourTurn,
+    /// An encoded Board couldn't be decoded, or isn't a position the
+    /// Players could have reached by taking turns.
+    MalformedBoard,
+    /// The game board would have more cells than the server allows.
+    BoardTooLarge {
+        height: usize,
+        width: usize,
+        max_cells: usize,
+    },
+    /// There's no unfinished game saved under the given id.
+    NoUnfinishedGame { id: i64 },
+    /// A saved game can't be resumed without the database.
+    PersistenceDisabled,
+    /// Verifying the saved games turned up some that don't hold up.
+    GamesFailedVerification { count: usize },
+    /// Some of the games given to import couldn't have been played.
+    GamesFailedImport { count: usize },
     /// An error occurred while serializing or deserializing.
     SerializationError { source: JsonError },
     /// An error occurred with the database.
@@ -24,10 +58,11 @@ pub enum ServerError {
 impl fmt::Display for ServerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ServerError::GameFull => write!(
-                f,
-                "Game is at max capacity and can't accept any more players 😞"
-            ),
+            ServerError::GameFull => write!(f, "{}", GAME_FULL_MESSAGE),
+            ServerError::GameInProgress => write!(f, "The game is already in progress."),
+            ServerError::FullRow => write!(f, "That row is full."),
+            ServerError::NonexistentRow => write!(f, "That row doesn't exist."),
+            ServerError::BlockedSide => write!(f, "That row can't be played from that side."),
             ServerError::IoError { source } => write!(f, "An I/O error occurred: {}", source),
             ServerError::CodecError { source } => write!(
                 f,
@@ -35,11 +70,36 @@ impl fmt::Display for ServerError {
                 source
             ),
             ServerError::NotYourTurn => write!(f, "It isn't your turn!"),
+            ServerError::MalformedBoard => write!(
+                f,
+                "The encoded board is malformed. Rows must be separated by '/' and contain only '_', 'X', or 'O', and the Players must have taken turns."
+            ),
             ServerError::SerializationError { source } => write!(
                 f,
                 "An error occurred while serializing or deserializing: {}",
                 source
             ),
+            ServerError::BoardTooLarge {
+                height,
+                width,
+                max_cells,
+            } => write!(
+                f,
+                "A {}x{} board is too large; at most {} cells are allowed.",
+                height, width, max_cells
+            ),
+            ServerError::NoUnfinishedGame { id } => {
+                write!(f, "There's no unfinished game saved with id {}.", id)
+            }
+            ServerError::PersistenceDisabled => {
+                write!(f, "Saved games can't be resumed with --no-persist.")
+            }
+            ServerError::GamesFailedVerification { count } => {
+                write!(f, "{} saved games failed verification.", count)
+            }
+            ServerError::GamesFailedImport { count } => {
+                write!(f, "{} games couldn't be imported.", count)
+            }
             ServerError::DatabaseError { source } => {
                 write!(f, "An error occurred with the database: {}", source)
             }
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{error::ServerError, Player, Ruleset, Side, Turn};
+
+/// The possible variants of a single slot in a Board.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    /// A blank slot owned by neither player.
+    Blank,
+    /// A slot owned by the player playing X.
+    X,
+    /// A slot owned by the player playing O.
+    O,
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Slot::Blank => write!(f, "_"),
+            Slot::X => write!(f, "X"),
+            Slot::O => write!(f, "O"),
+        }
+    }
+}
+
+/// A Row of the Board.
+#[derive(Debug, Clone)]
+pub struct Row(Vec<Slot>);
+
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[ ")?;
+
+        for slot in self.0.iter() {
+            write!(f, "{} ", slot)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// The server's authoritative copy of the game board. This mirrors the
+/// client's Board, keeping only what the server needs to referee a game.
+#[derive(Debug, Clone)]
+pub struct Board {
+    rows: Vec<Row>,
+    pub height: usize,
+    pub width: usize,
+    /// How many consecutive pieces a Player needs to win.
+    pub win_length: usize,
+    /// Whether pieces fall to the bottom of their column once inserted.
+    pub gravity: bool,
+    /// Whether the first Player plays `O` and the second plays `X`.
+    pub swap_symbols: bool,
+    /// The only Side each of these Rows may be played from.
+    pub side_restrictions: HashMap<usize, Side>,
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row_num, row) in self.rows.iter().enumerate() {
+            writeln!(f, "{} {}", row_num, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Board {
+    /// Initializes a new, empty Board played under the given Ruleset.
+    pub fn new(ruleset: &Ruleset) -> Self {
+        let Ruleset { height, width, .. } = *ruleset;
+
+        Self {
+            rows: (0..height)
+                .map(|_| Row((0..width).map(|_| Slot::Blank).collect::<Vec<_>>()))
+                .collect::<Vec<_>>(),
+            height,
+            width,
+            win_length: ruleset.win_length,
+            gravity: ruleset.gravity,
+            swap_symbols: ruleset.swap_symbols,
+            side_restrictions: ruleset.side_restrictions.clone(),
+        }
+    }
+
+    /// Rebuild a Board played under the given Ruleset by replaying the
+    /// given Turns in order.
+    pub fn from_turns(ruleset: &Ruleset, turns: &[Turn]) -> Result<Self, ServerError> {
+        Self::new(ruleset).apply_turns(turns)
+    }
+
+    /// Replay the given Turns in order on top of this Board.
+    pub fn apply_turns(mut self, turns: &[Turn]) -> Result<Self, ServerError> {
+        for turn in turns {
+            self.apply_turn(turn)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Decode a Board written by `encode`, to be played under the given
+    /// Ruleset. The Board takes its size from the encoding rather than the
+    /// Ruleset.
+    pub fn decode(ruleset: &Ruleset, encoded: &str) -> Result<Self, ServerError> {
+        let rows = encoded
+            .trim()
+            .split('/')
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '_' => Ok(Slot::Blank),
+                        'X' => Ok(Slot::X),
+                        'O' => Ok(Slot::O),
+                        _ => Err(ServerError::MalformedBoard),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Row)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = rows.first().map_or(0, |row| row.0.len());
+        if width == 0 || rows.iter().any(|row| row.0.len() != width) {
+            return Err(ServerError::MalformedBoard);
+        }
+
+        let mut board = Self::new(&Ruleset {
+            height: rows.len(),
+            width,
+            ..ruleset.clone()
+        });
+        board.rows = rows;
+
+        Ok(board)
+    }
+
+    /// Encode the Board compactly as its Rows separated by `/`, with each
+    /// Slot written as it is displayed, e.g. `X__/O__/___`.
+    pub fn encode(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| row.0.iter().map(Slot::to_string).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Returns whether every Row of the Board is full.
+    pub fn is_full(&self) -> bool {
+        self.rows
+            .iter()
+            .all(|row| row.0.iter().all(|slot| *slot != Slot::Blank))
+    }
+
+    /// The share of the Board's Slots that are occupied, from 0 for an
+    /// empty Board to 1 for a full one.
+    pub fn fill_ratio(&self) -> f64 {
+        let cells = self.height * self.width;
+        if cells == 0 {
+            return 1.0;
+        }
+
+        let filled = self
+            .rows
+            .iter()
+            .flat_map(|row| row.0.iter())
+            .filter(|slot| **slot != Slot::Blank)
+            .count();
+
+        filled as f64 / cells as f64
+    }
+
+    /// Returns whether either Player could still win, i.e. whether some
+    /// line of `win_length` Slots holds only blanks and the pieces of at
+    /// most one Player.
+    pub fn can_anyone_win(&self) -> bool {
+        let length = self.win_length as isize;
+
+        let open = |row_num: isize, col: isize, d_row: isize, d_col: isize| {
+            let mut owner = None;
+
+            (0..length).all(|i| {
+                let (r, c) = (row_num + d_row * i, col + d_col * i);
+                if r < 0 || c < 0 {
+                    return false;
+                }
+
+                let slot = self
+                    .rows
+                    .get(r as usize)
+                    .and_then(|row| row.0.get(c as usize));
+                match slot {
+                    Some(Slot::Blank) => true,
+                    Some(slot) => owner.get_or_insert(*slot) == slot,
+                    None => false,
+                }
+            })
+        };
+
+        (0..self.height as isize).any(|row_num| {
+            (0..self.width as isize).any(|col| {
+                [(1, 0), (0, 1), (-1, 1), (1, 1)]
+                    .iter()
+                    .any(|&(d_row, d_col)| open(row_num, col, d_row, d_col))
+            })
+        })
+    }
+
+    /// The Player, if any, with a winning line running through the given
+    /// coordinates.
+    pub fn winner_at(&self, row_num: usize, col: usize) -> Option<Player> {
+        let slot = *self.rows.get(row_num)?.0.get(col)?;
+        let player = self.owner(slot)?;
+
+        let matches = |row_num: isize, col: isize| {
+            row_num >= 0
+                && col >= 0
+                && self
+                    .rows
+                    .get(row_num as usize)
+                    .and_then(|row| row.0.get(col as usize))
+                    == Some(&slot)
+        };
+
+        // count the run through the coordinates along each of the four axes
+        // a line can run
+        let won = [(1, 0), (0, 1), (-1, 1), (1, 1)].iter().any(|&(d_row, d_col)| {
+            let mut length = 1;
+
+            for sign in [-1, 1].iter() {
+                let (mut r, mut c) = (row_num as isize, col as isize);
+
+                while matches(r + d_row * sign, c + d_col * sign) {
+                    r += d_row * sign;
+                    c += d_col * sign;
+                    length += 1;
+                }
+            }
+
+            length >= self.win_length
+        });
+
+        if won {
+            Some(player)
+        } else {
+            None
+        }
+    }
+
+    /// Insert the Slot belonging to the Turn's Player from the Turn's side.
+    /// Returns the coordinates of the spot that becomes occupied.
+    pub fn apply_turn(&mut self, turn: &Turn) -> Result<(usize, usize), ServerError> {
+        let slot = self.slot_for(turn.source);
+
+        if !self.side_allowed(turn.mov.row, turn.mov.side) {
+            return Err(ServerError::BlockedSide);
+        }
+
+        let row = self
+            .rows
+            .get_mut(turn.mov.row)
+            .ok_or(ServerError::NonexistentRow)?;
+
+        // the same scan order as the client's `insert_from_left` and
+        // `insert_from_right`
+        let col = match turn.mov.side {
+            Side::Left => row.0.iter().rposition(|spot| *spot == Slot::Blank),
+            Side::Right => row.0.iter().position(|spot| *spot == Slot::Blank),
+        }
+        .ok_or(ServerError::FullRow)?;
+
+        row.0[col] = slot;
+
+        Ok(self.settle(turn.mov.row, col))
+    }
+
+    /// Iterate over the Rows of the Board, from the top down.
+    pub fn rows_iter(&self) -> impl Iterator<Item = &Row> {
+        self.rows.iter()
+    }
+
+    /// The Slot the given Player's pieces are shown as.
+    pub fn slot_for(&self, player: Player) -> Slot {
+        player.symbol(self.swap_symbols)
+    }
+
+    /// Returns whether the given Row may be played from the given Side.
+    pub fn side_allowed(&self, row_num: usize, side: Side) -> bool {
+        self.side_restrictions
+            .get(&row_num)
+            .is_none_or(|allowed| *allowed == side)
+    }
+
+    /// Count the pieces the given Player has placed on the Board.
+    pub fn count(&self, player: Player) -> usize {
+        let slot = self.slot_for(player);
+
+        self.rows
+            .iter()
+            .flat_map(|row| row.0.iter())
+            .filter(|spot| **spot == slot)
+            .count()
+    }
+
+    /// Work out whose turn it is from how many pieces each Player has
+    /// placed, given which Player moved first. Returns `None` if the counts
+    /// are too far apart for the Players to have taken turns.
+    pub fn to_move(&self, first_player: Player) -> Option<Player> {
+        match self
+            .count(first_player)
+            .checked_sub(self.count(!first_player))
+        {
+            Some(0) => Some(first_player),
+            Some(1) => Some(!first_player),
+            _ => None,
+        }
+    }
+
+    /// The Player whose pieces are shown as the given Slot, if any.
+    pub fn owner(&self, slot: Slot) -> Option<Player> {
+        match slot {
+            Slot::Blank => None,
+            _ if slot == self.slot_for(Player::First) => Some(Player::First),
+            _ => Some(Player::Second),
+        }
+    }
+
+    /// Clear the Slot at the given coordinates, taking back the Turn that
+    /// occupied it. Coordinates outside the Board are ignored.
+    pub fn clear(&mut self, row_num: usize, col: usize) {
+        if let Some(spot) = self.rows.get_mut(row_num).and_then(|row| row.0.get_mut(col)) {
+            *spot = Slot::Blank;
+        }
+    }
+
+    /// Drop the Slot at the given coordinates to the bottom of its column
+    /// if gravity is on. Returns the coordinates the Slot comes to rest at.
+    fn settle(&mut self, mut row_num: usize, col: usize) -> (usize, usize) {
+        if !self.gravity {
+            return (row_num, col);
+        }
+
+        while row_num + 1 < self.height && self.rows[row_num + 1].0[col] == Slot::Blank {
+            self.rows[row_num + 1].0[col] = self.rows[row_num].0[col];
+            self.rows[row_num].0[col] = Slot::Blank;
+            row_num += 1;
+        }
+
+        (row_num, col)
+    }
+}
@@ -1,26 +1,99 @@
 use futures::{sink::SinkExt, StreamExt};
 use std::collections::HashMap;
+use std::fmt::{self, Write};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use rusqlite::Connection;
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use rusqlite::{Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
-use tokio::net::TcpStream;
-use tokio::sync::{mpsc, Mutex};
-use tokio_util::codec::{Framed, LinesCodec};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::Instant;
+use tokio_util::codec::Framed;
+use uuid::Uuid;
 
 use crate::error::ServerError;
+use crate::game::{Board, Slot};
+use crate::log::{Event, LogFormat, Logger};
+use crate::series::Match;
+use crate::transcript::{Transcript, TranscriptCodec};
 
 pub mod error;
+pub mod game;
+pub mod log;
+pub mod series;
+pub mod transcript;
+pub mod verify;
+
+/// The major version of the protocol spoken between client and server. Bump
+/// this whenever a Request or Response changes in a way older builds can't
+/// understand, and reset the minor version.
+pub const PROTOCOL_VERSION: u32 = 21;
+
+/// The minor version of the protocol. Bump this for changes older builds
+/// can safely ignore, such as a new optional field. Clients and servers
+/// with different minor versions still play together.
+pub const PROTOCOL_MINOR_VERSION: u32 = 1;
 
 static DB_PATH: &str = "../db/games.db";
 
+/// The most recently assigned client id, shared by every listener.
+static LAST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// How often the idle reaper checks whether the game has gone idle.
+const REAP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Columns added to the `games` table after its initial schema. Each
+/// statement fails harmlessly once it has already been applied.
+static MIGRATIONS: &[&str] = &[
+    "ALTER TABLE games ADD COLUMN thinking_time TEXT",
+    "ALTER TABLE games ADD COLUMN final_board TEXT",
+    "ALTER TABLE games ADD COLUMN result TEXT",
+    "ALTER TABLE games ADD COLUMN start_position TEXT",
+    "ALTER TABLE games ADD COLUMN uuid TEXT",
+    "CREATE TABLE IF NOT EXISTS matches (
+        id INTEGER PRIMARY KEY,
+        games_to_win INTEGER NOT NULL,
+        scores TEXT NOT NULL,
+        winner TEXT NOT NULL
+    )",
+    "ALTER TABLE games ADD COLUMN ruleset TEXT",
+];
+
 /// Sender half of the message channel.
-type Tx = mpsc::UnboundedSender<String>;
+type Tx = mpsc::Sender<String>;
 
 /// Receiver half of the message channel.
-type Rx = mpsc::UnboundedReceiver<String>;
+type Rx = mpsc::Receiver<String>;
+
+/// Uniquely identifies a client connection, whatever transport it uses.
+pub type ClientId = u64;
+
+/// A bidirectional byte stream to a client, whatever transport it uses.
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// A client's stream, framed as lines.
+type Lines = Framed<Box<dyn Stream>, TranscriptCodec>;
+
+/// Frame a client's stream as lines, writing each one to the transcript if
+/// there is one.
+fn frame<S: Stream + 'static>(stream: S, id: ClientId, transcript: Option<Transcript>) -> Lines {
+    Framed::new(
+        Box::new(stream),
+        TranscriptCodec::new(transcript, format!("client={}", id)),
+    )
+}
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -29,23 +102,251 @@ type Rx = mpsc::UnboundedReceiver<String>;
 )]
 pub enum Server {
     Start(Params),
+    /// Continue an unfinished game saved in the database, under the rules
+    /// it was saved with
+    Resume {
+        /// The id the game was saved with.
+        id: i64,
+        #[structopt(flatten)]
+        params: Params,
+    },
+    /// Replay every game saved in the database and report any that don't
+    /// hold up, e.g. because they were saved by a buggy version. Games are
+    /// replayed under the rules given.
+    Verify {
+        #[structopt(flatten)]
+        params: Params,
+    },
+    /// Save the games in the given files, each a JSON list of Turns, to the
+    /// database, reporting any whose Turns couldn't have been played under
+    /// the rules given
+    Import {
+        /// The game files to import.
+        #[structopt(parse(from_os_str), required = true)]
+        paths: Vec<PathBuf>,
+        /// Only check the games and report on each one, without saving
+        /// anything.
+        #[structopt(long)]
+        dry_run: bool,
+        #[structopt(flatten)]
+        params: Params,
+    },
+}
+
+/// How the server decides which Player moves after each Turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnPolicy {
+    /// The Players take turns, one after the other.
+    Alternate,
+    /// Whoever moves next is drawn from the seeded RNG after every Turn.
+    RandomEachTurn,
+}
+
+impl FromStr for TurnPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "alternate" => Ok(TurnPolicy::Alternate),
+            "random" => Ok(TurnPolicy::RandomEachTurn),
+            _ => Err(format!(
+                "Unknown turn order '{}', expected 'alternate' or 'random'",
+                s
+            )),
+        }
+    }
 }
 
 /// CLI Params that the server accepts from the user.
 #[derive(Debug, StructOpt)]
 pub struct Params {
-    /// The height of the game board.
-    #[structopt(short, long, default_value = "7")]
-    pub height: usize,
-    /// The width of the game board.
-    #[structopt(short, long, default_value = "7")]
-    pub width: usize,
+    /// Start from a named set of rules: `classic`, `blitz`, or `marathon`.
+    /// The flags for individual rules override it.
+    #[structopt(long, parse(try_from_str = Ruleset::preset))]
+    pub preset: Option<Ruleset>,
+    /// The height of the game board. Defaults to 7.
+    #[structopt(short, long)]
+    pub height: Option<usize>,
+    /// The width of the game board. Defaults to 7.
+    #[structopt(short, long)]
+    pub width: Option<usize>,
+    /// How many consecutive pieces a Player needs to win. Defaults to 4.
+    #[structopt(long)]
+    pub win_length: Option<usize>,
+    /// How long each Player has to take their Turn before the game is
+    /// closed, in seconds.
+    #[structopt(long)]
+    pub turn_timeout_secs: Option<u64>,
+    /// Only allow a Row to be played from one Side, written as
+    /// `ROW=SIDE`, e.g. `1=left`. May be given once for each Row.
+    #[structopt(
+        long = "restrict-side",
+        value_name = "ROW=SIDE",
+        number_of_values = 1,
+        parse(try_from_str = parse_side_restriction)
+    )]
+    pub side_restrictions: Vec<(usize, Side)>,
+    /// Start the game from this encoded Board instead of an empty one,
+    /// e.g. `X__/O__/___`. The Board's size overrides `height` and
+    /// `width`, and whose turn it is follows from the pieces placed.
+    #[structopt(long)]
+    pub start_position: Option<String>,
+    /// The id of an unfinished game to pick up where it left off, as set
+    /// by the `resume` subcommand.
+    #[structopt(skip)]
+    pub resume: Option<i64>,
+    /// Which Player moves first, either 1 or 2.
+    #[structopt(long, default_value = "1")]
+    pub first_player: u32,
+    /// Make pieces fall to the bottom of their column once inserted.
+    #[structopt(long)]
+    pub gravity: bool,
+    /// Flip a coin to decide which Player plays `X` and which plays `O`,
+    /// rather than the first Player always playing `X`.
+    #[structopt(long)]
+    pub swap_symbols: bool,
+    /// Seed for the server's random choices, so that they can be
+    /// reproduced. Drawn at random if this isn't set.
+    #[structopt(long)]
+    pub seed: Option<u64>,
+    /// How to decide who moves after each Turn, either `alternate` or
+    /// `random`.
+    #[structopt(long, default_value = "alternate")]
+    pub turn_order: TurnPolicy,
     /// The Address for the server to listen on.
     #[structopt(short, long, default_value = "0.0.0.0:8080")]
     pub addr: SocketAddr,
+    /// Listen on a Unix domain socket at this path instead of on `addr`.
+    #[structopt(long, parse(from_os_str))]
+    pub unix_socket: Option<PathBuf>,
+    /// Also listen on this address for read-only observers, who are shown
+    /// the game without being able to join it.
+    #[structopt(long)]
+    pub observer_addr: Option<SocketAddr>,
+    /// Also listen on this address for health checks, answering every
+    /// connection with `OK` whatever state the game is in.
+    #[structopt(long)]
+    pub health_addr: Option<SocketAddr>,
+    /// How long to hold a disconnected Player's seat open for them to
+    /// rejoin before ending the game. Disconnects end the game
+    /// immediately if this isn't set.
+    #[structopt(long)]
+    pub reconnect_grace_secs: Option<u64>,
+    /// How long a reconnect token stays valid after it's issued, in
+    /// seconds. Expired tokens are swept away, freeing any seat held for
+    /// them. Tokens don't expire if this isn't set.
+    #[structopt(long)]
+    pub token_ttl_secs: Option<u64>,
+    /// A name for this server, shown to clients when they connect.
+    #[structopt(long)]
+    pub server_name: Option<String>,
+    /// Let a single client hold both seats and play both sides, for
+    /// testing and demos.
+    #[structopt(long)]
+    pub allow_self_play: bool,
+    /// Also record games that were abandoned before both Players joined or
+    /// before anyone took a Turn. Such games are skipped otherwise.
+    #[structopt(long)]
+    pub persist_on_disconnect: bool,
+    /// Don't save anything, or even open the database, so that the server
+    /// leaves nothing on disk.
+    #[structopt(long)]
+    pub no_persist: bool,
+    /// Log every Turn that is played, with where it landed, e.g.
+    /// `game=1 ply=3 First 2R -> (2,0)`.
+    #[structopt(long)]
+    pub verbose_turns: bool,
+    /// Append every line sent to or received from a client to this file,
+    /// for debugging.
+    #[structopt(long, parse(from_os_str))]
+    pub transcript: Option<PathBuf>,
+    /// Play a match of this many games instead of a single game, won by
+    /// whoever wins the majority of them. The Players take turns moving
+    /// first, and a match that runs out of games goes to whoever won more
+    /// of them, or is drawn.
+    #[structopt(long)]
+    pub best_of: Option<u32>,
+    /// Suppress informational output. Errors are still reported.
+    #[structopt(short, long)]
+    pub quiet: bool,
+    /// How to write the server's log, either `text` or `json`.
+    #[structopt(long, default_value = "text")]
+    pub log_format: LogFormat,
+    /// How many outgoing messages may be queued for a client before it is
+    /// considered too slow and dropped.
+    #[structopt(long, default_value = "32")]
+    pub channel_capacity: usize,
+    /// The most spectators that may watch the game at once. Unlimited if
+    /// this isn't set.
+    #[structopt(long)]
+    pub spectator_limit: Option<usize>,
+    /// The most cells the game board may have, so that a huge board is
+    /// refused up front rather than exhausting memory.
+    #[structopt(long, default_value = "10000")]
+    pub max_cells: usize,
+    /// The most clients that may be connected at once, counting Players,
+    /// spectators, and observers. Unlimited if this isn't set.
+    #[structopt(long)]
+    pub max_connections: Option<usize>,
+    /// Close the game once it has gone this long without anyone joining
+    /// or taking a Turn. Games never time out if this isn't set.
+    #[structopt(long)]
+    pub idle_timeout_secs: Option<u64>,
+}
+
+impl Params {
+    /// The rules of the game these Params describe. With `swap_symbols`
+    /// set, which Player plays which symbol is drawn from the seeded RNG.
+    pub fn ruleset(&self) -> Ruleset {
+        let mut rng = self.rng();
+
+        let preset = self.preset.clone().unwrap_or_default();
+
+        let mut side_restrictions = preset.side_restrictions;
+        side_restrictions.extend(self.side_restrictions.iter().copied());
+
+        Ruleset {
+            height: self.height.unwrap_or(preset.height),
+            width: self.width.unwrap_or(preset.width),
+            win_length: self.win_length.unwrap_or(preset.win_length),
+            first_player: Player::from(self.first_player),
+            gravity: self.gravity || preset.gravity,
+            swap_symbols: (self.swap_symbols || preset.swap_symbols) && rng.random_bool(0.5),
+            turn_timeout_secs: self.turn_timeout_secs.or(preset.turn_timeout_secs),
+            side_restrictions,
+        }
+    }
+
+    /// An RNG seeded with `seed`, or at random if it isn't set.
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        }
+    }
+}
+
+/// Parse a `ROW=SIDE` restriction on the Side a Row may be played from.
+fn parse_side_restriction(restriction: &str) -> Result<(usize, Side), String> {
+    let error = || {
+        format!(
+            "Invalid side restriction '{}', expected e.g. '1=left' or '2=right'",
+            restriction
+        )
+    };
+
+    let (row, side) = restriction.split_once('=').ok_or_else(error)?;
+    let row = row.trim().parse().map_err(|_| error())?;
+    let side = match side.trim().to_lowercase().as_str() {
+        "l" | "left" => Side::Left,
+        "r" | "right" => Side::Right,
+        _ => return Err(error()),
+    };
+
+    Ok((row, side))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Player {
     /// First Player
     First,
@@ -53,6 +354,37 @@ pub enum Player {
     Second,
 }
 
+impl Player {
+    /// Where this Player's entry goes in an array holding something for
+    /// each Player: 0 for the first Player and 1 for the second.
+    pub fn index(self) -> usize {
+        match self {
+            Player::First => 0,
+            Player::Second => 1,
+        }
+    }
+
+    /// The Player whose entry is at the given index of an array holding
+    /// something for each Player, if any.
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Player::First),
+            1 => Some(Player::Second),
+            _ => None,
+        }
+    }
+
+    /// The Slot this Player's pieces are shown as: `X` for the first Player
+    /// and `O` for the second, or the other way around if the symbols are
+    /// swapped.
+    pub fn symbol(self, swap_symbols: bool) -> Slot {
+        match (self, swap_symbols) {
+            (Player::First, false) | (Player::Second, true) => Slot::X,
+            (Player::Second, false) | (Player::First, true) => Slot::O,
+        }
+    }
+}
+
 impl std::ops::Not for Player {
     type Output = Self;
 
@@ -64,6 +396,15 @@ impl std::ops::Not for Player {
     }
 }
 
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Player::First => write!(f, "First"),
+            Player::Second => write!(f, "Second"),
+        }
+    }
+}
+
 // TODO: Make this a `try_from`
 impl From<u32> for Player {
     fn from(n: u32) -> Self {
@@ -75,275 +416,2793 @@ impl From<u32> for Player {
     }
 }
 
+/// Every rule that both sides of a game need to agree on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ruleset {
+    /// The height of the game board.
+    pub height: usize,
+    /// The width of the game board.
+    pub width: usize,
+    /// How many consecutive pieces a Player needs to win.
+    pub win_length: usize,
+    /// The Player who moves first.
+    pub first_player: Player,
+    /// Whether pieces fall to the bottom of their column once inserted.
+    pub gravity: bool,
+    /// Whether the first Player plays `O` and the second plays `X`.
+    #[serde(default)]
+    pub swap_symbols: bool,
+    /// How long each Player has to take their Turn before the game is
+    /// closed, in seconds. There's no limit if this isn't set.
+    #[serde(default)]
+    pub turn_timeout_secs: Option<u64>,
+    /// The only Side each of these Rows may be played from. Rows that
+    /// aren't listed may be played from either Side.
+    #[serde(default)]
+    pub side_restrictions: HashMap<usize, Side>,
+}
+
+impl Ruleset {
+    /// The standard game: connect four on a 7x7 Board.
+    pub fn standard() -> Self {
+        Ruleset {
+            height: 7,
+            width: 7,
+            win_length: 4,
+            first_player: Player::First,
+            gravity: false,
+            swap_symbols: false,
+            turn_timeout_secs: None,
+            side_restrictions: HashMap::new(),
+        }
+    }
+
+    /// The `classic` preset, the same as the standard game.
+    pub fn classic() -> Self {
+        Self::standard()
+    }
+
+    /// The `blitz` preset: connect four on a 5x5 Board, with 10 seconds
+    /// for each Turn.
+    pub fn blitz() -> Self {
+        Ruleset {
+            height: 5,
+            width: 5,
+            turn_timeout_secs: Some(10),
+            ..Self::standard()
+        }
+    }
+
+    /// The `marathon` preset: connect five on a 9x9 Board.
+    pub fn marathon() -> Self {
+        Ruleset {
+            height: 9,
+            width: 9,
+            win_length: 5,
+            ..Self::standard()
+        }
+    }
+
+    /// Look up a preset by name.
+    pub fn preset(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "classic" => Ok(Self::classic()),
+            "blitz" => Ok(Self::blitz()),
+            "marathon" => Ok(Self::marathon()),
+            _ => Err(format!(
+                "Unknown preset '{}', expected 'classic', 'blitz', or 'marathon'",
+                name
+            )),
+        }
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// How a game ended, from the point of view of one Player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameResult {
+    /// The given Player won.
+    Win(Player),
+    /// The Board filled up without anyone winning.
+    Tie,
+    /// The other Player left before the game was over.
+    OpponentLeft,
+    /// This Player quit before the game was over.
+    LocalQuit,
+    /// The given Player resigned before the game was over.
+    Resigned(Player),
+}
+
 /// The sides from which Players may choose to insert a slot.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Side {
     Left,
     Right,
 }
 
 /// A Player's move.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Move {
     side: Side,
     row: usize,
 }
 
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "L"),
+            Side::Right => write!(f, "R"),
+        }
+    }
+}
+
 /// A Player's turn.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Turn {
     source: Player,
     mov: Move,
 }
 
-/// Requests the server receives from clients.
-#[derive(Debug, Serialize, Deserialize)]
+impl fmt::Display for Turn {
+    /// Written compactly as the Player followed by their Move, e.g.
+    /// `First 3R`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}{}", self.source, self.mov.row, self.mov.side)
+    }
+}
+
+/// Requests the server receives from clients. New variants may be added
+/// as the protocol grows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Request {
     /// A client requests to join the game.
     Join,
+    /// A client requests to reclaim the seat it held before disconnecting,
+    /// with the token it was given in its `Welcome`.
+    Rejoin { player: Player, token: String },
+    /// A client requests to watch the game without playing.
+    Spectate,
+    /// A client requests the status of the game without joining it.
+    Status,
     /// A client submits a `Turn` action.
     Turn(Turn),
+    /// A Player asks to take back the last exchange of Turns.
+    Undo,
+    /// A Player answers the other Player's request to undo.
+    UndoReply { accept: bool },
+    /// A Player concedes the game to the other Player.
+    Resign,
+    /// A client requests to hold both seats and play both sides. Only
+    /// servers started with `--allow-self-play` accept this.
+    SelfPlay,
+    /// A client asks for the rendered board with every Turn, for thin
+    /// clients that don't keep track of the board themselves. Sent as the
+    /// first message, it also joins the game like `Join`.
+    ThinClient,
+}
+
+/// The kinds of error the server reports, so that clients can tell them
+/// apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// The Turn can't be played on the Board.
+    InvalidMove,
+    /// A Player attempted to act out of turn, or on behalf of the other
+    /// Player.
+    NotYourTurn,
+    /// The client is sending messages too quickly.
+    RateLimited,
+    /// The server doesn't allow what the client asked for.
+    Forbidden,
+    /// Something went wrong on the server.
+    Internal,
+    /// The server already has as many connections as it allows.
+    ServerFull,
 }
 
-/// The server's responses to client requests.
-#[derive(Debug, Serialize, Deserialize)]
+/// The server's responses to client requests. New variants may be added
+/// as the protocol grows, and clients skip any they don't recognize.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Response {
     /// There is enough capacity in the game. Tell the client which
     /// Player they are and the size of the board.
     Welcome {
         player: Player,
-        height: usize,
-        width: usize,
+        ruleset: Ruleset,
+        /// Missing from servers that predate versioning, which then count
+        /// as version 0.
+        #[serde(default)]
+        protocol_version: u32,
+        /// Missing from servers that predate minor versions, which then
+        /// count as minor version 0.
+        #[serde(default)]
+        protocol_minor_version: u32,
+        /// The name the server was given to tell it apart from others.
+        #[serde(default)]
+        server_name: Option<String>,
+        /// The encoded Board the game started from, if it didn't start
+        /// from an empty one.
+        #[serde(default)]
+        start_position: Option<String>,
+        /// The secret the Player presents to reclaim their seat after
+        /// disconnecting, if the server holds seats open for them.
+        #[serde(default)]
+        reconnect_token: Option<String>,
+        /// How many games the match being played lasts at most, if the
+        /// Players are playing a series of games rather than a single one.
+        #[serde(default)]
+        best_of: Option<u32>,
     },
     /// There are enough Players for the game to start.
     GameStart,
     /// There is not enough capacity in the game.
     GameFull,
-    /// A Player attempted to act out of turn.
-    NotYourTurn,
+    /// A client asked to play after the game had already started. It can
+    /// still be watched by spectating.
+    GameInProgress,
+    /// There is no room for any more spectators.
+    SpectatorFull,
+    /// The game was closed after going idle for too long.
+    IdleTimeout,
     /// Server sends the current Player's Turn to the other Player.
     Turn(Turn),
-    /// Server acknowledges a Player's proposed Turn.
-    Acknowledged,
+    /// Server sends the current Player's Turn to the other Player, along
+    /// with who moves next, when that isn't simply the other Player.
+    Played { turn: Turn, next_player: Player },
+    /// Server acknowledges a Player's proposed Turn, along with where it
+    /// landed and what it means for the game. `game_over` is `None` while
+    /// the game continues, `Some(None)` for a tie, and `Some(Some(player))`
+    /// for a win. `board` is the rendered board after the Turn, sent only
+    /// to thin clients.
+    Acknowledged {
+        coordinate: (usize, usize),
+        game_over: Option<Option<Player>>,
+        next_player: Player,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        board: Option<String>,
+    },
     /// The other Player disconnected.
     PlayerDisconnected,
-    /// An internal server error occurred.
-    ServerError,
+    /// A snapshot of the game for a client that starts watching it midway
+    /// or rejoins it, with the board encoded as its rows separated by `/`.
+    /// `display` is the board rendered for showing as is, for viewers that
+    /// don't decode it themselves. `turns` is the game's history of Turns,
+    /// for a rejoining Player who wants to take some of them back.
+    BoardState {
+        board: String,
+        #[serde(default)]
+        display: String,
+        current_player: Player,
+        #[serde(default)]
+        turns: Vec<Turn>,
+    },
+    /// The other Player asks to take back the last exchange of Turns.
+    UndoRequested,
+    /// A request to undo was turned down.
+    UndoDeclined,
+    /// The given number of most recent Turns were taken back.
+    Undone { plies: usize },
+    /// The game ended other than by a Turn, e.g. because a Player resigned.
+    /// `thinking_time` is the total time each Player spent deciding on their
+    /// Turns, by `Player::index`.
+    GameOver {
+        result: GameResult,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thinking_time: Option<[Duration; 2]>,
+    },
+    /// A game of a match ended without deciding it, and the next game
+    /// starts on a fresh board with the given Player moving first. `scores`
+    /// counts the games each Player has won so far, by `Player::index`.
+    NextGame {
+        first_player: Player,
+        scores: [u32; 2],
+    },
+    /// The match is over, won by the given Player, or drawn if `winner` is
+    /// `None` because it ran out of games with the scores level.
+    MatchOver {
+        winner: Option<Player>,
+        scores: [u32; 2],
+    },
+    /// The server couldn't do what the client asked, for the reason given
+    /// by `code`.
+    Error { code: ErrorCode, message: String },
+    /// The status of the game, for clients that aren't part of it.
+    Status {
+        players: usize,
+        spectators: usize,
+        in_progress: bool,
+        board: (usize, usize),
+    },
+}
+
+/// A token a seated Player can present to reclaim their seat after
+/// disconnecting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectToken {
+    /// The token itself, as random hex digits.
+    pub token: String,
+    /// When the token stops being accepted, if it ever does.
+    pub expires_at: Option<Instant>,
+}
+
+impl ReconnectToken {
+    /// Issue a fresh token, valid for the given time if it's limited.
+    fn issue(ttl: Option<Duration>) -> Self {
+        // drawn from the thread's cryptographically secure generator rather
+        // than the game's, which may be seeded and so predictable
+        let token = format!("{:032x}", rand::rng().random::<u128>());
+
+        ReconnectToken {
+            token,
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        }
+    }
+
+    /// Whether the token has expired as of `now`.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
 }
 
 /// Data and types that are shared between all peers playing the game.
 pub struct Shared {
     /// Handle to the database.
-    pub db_connection: Connection,
+    pub db_connection: Option<Connection>,
     /// Map of all Players and their send handles.
-    pub players: HashMap<SocketAddr, Tx>,
+    pub players: HashMap<ClientId, Tx>,
+    /// Map of all spectators and their send handles.
+    pub spectators: HashMap<ClientId, Tx>,
+    /// The seat each connected Player occupies.
+    pub seats: HashMap<ClientId, Player>,
+    /// Seats held open for disconnected Players, keyed by when they left.
+    pub pending: HashMap<Player, Instant>,
+    /// How long a disconnected Player's seat is held open for them.
+    pub reconnect_grace: Option<Duration>,
+    /// The token each seated Player must present to reclaim their seat.
+    pub reconnect_tokens: HashMap<Player, ReconnectToken>,
+    /// How long each reconnect token stays valid for.
+    pub token_ttl: Option<Duration>,
+    /// Writes the server's log.
+    pub logger: Logger,
+    /// The address each client connected from.
+    pub addrs: HashMap<ClientId, String>,
+    /// The id each client's connection was given when it was accepted, so
+    /// that it can be traced through the log.
+    pub connections: HashMap<ClientId, Uuid>,
+    /// How many outgoing messages may be queued for each client.
+    pub channel_capacity: usize,
+    /// The most spectators that may watch the game at once.
+    pub spectator_limit: Option<usize>,
+    /// How long the game may go without activity before it is closed.
+    pub idle_timeout: Option<Duration>,
+    /// When a Player last joined or took a Turn.
+    pub last_activity: Instant,
     /// Indicates which Player's turn it is.
     pub current_player: Player,
     /// The Turns taken by the Players over the course of a game.
     pub turns: Vec<Turn>,
-    /// The height of the game board.
-    pub height: usize,
-    /// The width of the game board.
-    pub width: usize,
+    /// The authoritative state of the game board.
+    pub board: Board,
+    /// The coordinates each Turn landed at, in the order they were taken.
+    pub placed: Vec<(usize, usize)>,
+    /// The Player waiting on an answer to their request to undo, if any.
+    pub undo_requested_by: Option<Player>,
+    /// How the game ended, once it has.
+    pub result: Option<GameResult>,
+    /// When the current Player was prompted for their Turn.
+    pub turn_started: Instant,
+    /// The total time each Player has spent deciding on their Turns, by
+    /// `Player::index`.
+    pub thinking_time: [Duration; 2],
+    /// The rules of the game.
+    pub ruleset: Ruleset,
+    /// The name shown to clients when they connect, if any.
+    pub server_name: Option<String>,
+    /// Whether both Players have been seated at once.
+    pub started: bool,
+    /// Whether a single client may hold both seats.
+    pub allow_self_play: bool,
+    /// The client holding both seats, if any.
+    pub self_play: Option<ClientId>,
+    /// Whether to record the game even if it was abandoned before it got
+    /// going.
+    pub persist_on_disconnect: bool,
+    /// The Board the game started from, if it didn't start from an empty
+    /// one.
+    pub start_position: Option<Board>,
+    /// The match being played, if the Players are playing a series of
+    /// games rather than a single one.
+    pub series: Option<Match>,
+    /// The number of the game being played, counting from 1. Only a match
+    /// plays more than one.
+    pub game: u32,
+    /// Identifies the game being played in the log and the database.
+    pub game_id: Uuid,
+    /// The id of the saved game this one was resumed from, if any, so that
+    /// it's saved over that game rather than saved again.
+    pub resumed_from: Option<i64>,
+    /// Whether to log every Turn that is played.
+    pub verbose_turns: bool,
+    /// How to decide who moves after each Turn.
+    pub turn_policy: TurnPolicy,
+    /// Draws whoever moves next under `TurnPolicy::RandomEachTurn`.
+    pub rng: StdRng,
+    /// Where every line sent to or received from a client is written, if
+    /// anywhere.
+    pub transcript: Option<Transcript>,
 }
 
 impl Shared {
     /// Attempt to create a new `Shared` instance.
-    pub fn try_new(height: usize, width: usize) -> Result<Self, ServerError> {
-        let db_connection = init_db()?;
+    pub fn try_new(params: &Params) -> Result<Self, ServerError> {
+        let db_connection = if params.no_persist {
+            None
+        } else {
+            Some(init_db()?)
+        };
+        let mut ruleset = params.ruleset();
+
+        let start_position = match &params.start_position {
+            Some(encoded) => Some(Board::decode(&ruleset, encoded)?),
+            None => None,
+        };
+        if let Some(board) = &start_position {
+            ruleset.height = board.height;
+            ruleset.width = board.width;
+        }
+
+        // check the size before allocating a Board that might not fit
+        if ruleset.height.saturating_mul(ruleset.width) > params.max_cells {
+            return Err(ServerError::BoardTooLarge {
+                height: ruleset.height,
+                width: ruleset.width,
+                max_cells: params.max_cells,
+            });
+        }
+
+        let board = match &start_position {
+            Some(board) => board.clone(),
+            None => Board::new(&ruleset),
+        };
+        let current_player = board
+            .to_move(ruleset.first_player)
+            .ok_or(ServerError::MalformedBoard)?;
 
-        Ok(Shared {
+        let series = params
+            .best_of
+            .map(|games| Match::best_of(games, ruleset.first_player));
+
+        let transcript = match &params.transcript {
+            Some(path) => Some(Transcript::open(path)?),
+            None => None,
+        };
+
+        let mut shared = Shared {
             db_connection,
             players: HashMap::new(),
-            current_player: Player::First,
+            spectators: HashMap::new(),
+            seats: HashMap::new(),
+            pending: HashMap::new(),
+            reconnect_grace: params.reconnect_grace_secs.map(Duration::from_secs),
+            reconnect_tokens: HashMap::new(),
+            token_ttl: params.token_ttl_secs.map(Duration::from_secs),
+            logger: Logger {
+                format: params.log_format,
+                quiet: params.quiet,
+            },
+            addrs: HashMap::new(),
+            connections: HashMap::new(),
+            channel_capacity: params.channel_capacity,
+            spectator_limit: params.spectator_limit,
+            // a Turn not taken in time leaves the game idle
+            idle_timeout: params
+                .idle_timeout_secs
+                .or(ruleset.turn_timeout_secs)
+                .map(Duration::from_secs),
+            last_activity: Instant::now(),
+            current_player,
             turns: Vec::new(),
-            height,
-            width,
-        })
-    }
+            board,
+            placed: Vec::new(),
+            undo_requested_by: None,
+            result: None,
+            turn_started: Instant::now(),
+            thinking_time: [Duration::ZERO; 2],
+            ruleset,
+            server_name: params.server_name.clone(),
+            started: false,
+            allow_self_play: params.allow_self_play,
+            self_play: None,
+            persist_on_disconnect: params.persist_on_disconnect,
+            start_position,
+            series,
+            game: 1,
+            game_id: Uuid::new_v4(),
+            resumed_from: None,
+            verbose_turns: params.verbose_turns,
+            turn_policy: params.turn_order,
+            rng: params.rng(),
+            transcript,
+        };
 
-    /// Send a line-encoded message to every peer except the sender.
-    /// Reject the message if it isn't the current Player's turn.
-    async fn broadcast(&mut self, sender: SocketAddr, message: &str) {
-        for player in self.players.iter_mut() {
-            if *player.0 != sender {
-                let _ = player.1.send(message.into());
-            }
+        if let Some(id) = params.resume {
+            shared.resume(id)?;
         }
+
+        Ok(shared)
     }
 
-    /// Send a line-encoded message back to the original sender.
-    async fn back_to_sender(&mut self, sender: SocketAddr, message: &str) {
-        let player = self.players.get_mut(&sender).unwrap();
-        let _ = player.send(message.into());
+    /// Pick up the unfinished game saved with the given id where it left
+    /// off, with its history of Turns and thinking time, and whoever's turn
+    /// it was to move. Both seats are open for the Players to join again,
+    /// and the game is saved over the one it was resumed from. The game is
+    /// resumed under the Ruleset it was saved with, or under this server's
+    /// rules if it was saved without one.
+    pub fn resume(&mut self, id: i64) -> Result<(), ServerError> {
+        let db_connection = self
+            .db_connection
+            .as_ref()
+            .ok_or(ServerError::PersistenceDisabled)?;
+        let (turns, start_position, thinking_time, uuid, ruleset): (
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        ) = db_connection
+            .query_row(
+                "SELECT turns, start_position, thinking_time, uuid, ruleset FROM games WHERE id = ?1 AND (result IS NULL OR result = 'null')",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .optional()?
+            .ok_or(ServerError::NoUnfinishedGame { id })?;
+        let turns: Vec<Turn> = serde_json::from_str(&turns)?;
+        if let Some(ruleset) = ruleset {
+            self.ruleset = serde_json::from_str(&ruleset)?;
+        }
+
+        let start_position = match start_position {
+            Some(encoded) => Some(Board::decode(&self.ruleset, &encoded)?),
+            None => None,
+        };
+        let mut board = match &start_position {
+            Some(board) => board.clone(),
+            None => Board::new(&self.ruleset),
+        };
+        let placed = turns
+            .iter()
+            .map(|turn| board.apply_turn(turn))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.current_player = board
+            .to_move(self.ruleset.first_player)
+            .ok_or(ServerError::MalformedBoard)?;
+        self.ruleset.height = board.height;
+        self.ruleset.width = board.width;
+        self.start_position = start_position;
+        self.board = board;
+        self.turns = turns;
+        self.placed = placed;
+        if let Some(thinking_time) = thinking_time {
+            self.thinking_time = serde_json::from_str(&thinking_time)?;
+        }
+        // the game keeps the id it was logged with before
+        if let Some(uuid) = uuid.and_then(|uuid| Uuid::parse_str(&uuid).ok()) {
+            self.game_id = uuid;
+        }
+        self.resumed_from = Some(id);
+
+        Ok(())
     }
-}
 
-impl Drop for Shared {
-    fn drop(&mut self) {
-        println!("Saving game to database...");
+    /// Send a line-encoded message to every peer except the sender.
+    /// Reject the message if it isn't the current Player's turn.
+    fn broadcast(&mut self, sender: ClientId, message: &str) {
+        let recipients = self
+            .players
+            .keys()
+            .filter(|id| **id != sender)
+            .copied()
+            .collect::<Vec<_>>();
 
-        self.db_connection
-            .execute(
-                "INSERT INTO games (turns) values (?1)",
-                &[&serde_json::to_string(&self.turns).expect("Failed to serialize Turns.")],
-            )
-            .expect("Error: Failed to persist game to database.");
+        for id in recipients {
+            self.send_to(id, message);
+        }
     }
-}
 
-/// The state of each connected peer.
-pub struct Peer {
-    /// The Player's number, starting at 1.
-    number: u32,
-    /// The Peer's receiver handle.
-    rx: Rx,
-    /// Receive messages from players as lines, without having to worry
-    /// about working at the raw byte level.
-    lines: Framed<TcpStream, LinesCodec>,
-}
+    /// Queue a line-encoded message for a Player. A Player whose queue is
+    /// already full has fallen too far behind, so it is dropped instead of
+    /// letting its queue grow without bound.
+    fn send_to(&mut self, id: ClientId, message: &str) {
+        let dropped = match self.players.get(&id) {
+            Some(tx) => tx.try_send(message.into()).is_err(),
+            None => false,
+        };
 
-impl Peer {
-    /// Create a new `Peer` instance and notify the client.
-    async fn new(
-        state: Arc<Mutex<Shared>>,
-        mut lines: Framed<TcpStream, LinesCodec>,
-    ) -> Result<Option<Self>, ServerError> {
-        let addr = lines.get_ref().peer_addr()?;
-        let (tx, rx) = mpsc::unbounded_channel();
+        if dropped {
+            // dropping the send handle ends the Player's connection
+            self.players.remove(&id);
+            self.log_error(
+                Event::new(
+                    "dropped",
+                    format!("Dropped client {} for falling behind.", id),
+                )
+                .client(id),
+            );
+        }
+    }
 
-        let mut state = state.lock().await;
-        let num_players = state.players.len() as u32 + 1;
+    /// Fill in the game an Event happened in, along with the address and
+    /// connection of the client involved, if known.
+    fn with_context(&self, event: Event) -> Event {
+        let mut event = event.game(self.game_id.to_string());
 
-        if num_players > 2 {
-            let msg = serde_json::to_string(&Response::GameFull)?;
-            state.back_to_sender(addr, &msg).await;
-            return Ok(None);
+        if let Some(id) = event.client {
+            if let (Some(addr), None) = (self.addrs.get(&id), &event.addr) {
+                event = event.addr(addr.as_str());
+            }
+            if let Some(connection) = self.connections.get(&id) {
+                event = event.connection(connection.to_string());
+            }
         }
 
-        state.players.insert(addr, tx);
+        event
+    }
 
-        let (height, width) = (state.height, state.width);
-        let player = Player::from(num_players);
-        lines
-            .send(serde_json::to_string(&Response::Welcome {
-                player,
-                height,
-                width,
-            })?)
-            .await?;
+    /// Log an informational Event, unless quiet.
+    pub fn log(&self, event: Event) {
+        self.logger.info(self.with_context(event));
+    }
 
-        Ok(Some(Peer {
-            number: num_players,
-            lines,
-            rx,
-        }))
+    /// Log an error Event.
+    pub fn log_error(&self, event: Event) {
+        self.logger.error(self.with_context(event));
     }
-}
 
-/// Process an individual player client.
-pub async fn process(
-    state: Arc<Mutex<Shared>>,
-    stream: TcpStream,
-    addr: SocketAddr,
-) -> Result<(), ServerError> {
-    let lines = Framed::new(stream, LinesCodec::new());
-
-    let mut peer = match Peer::new(state.clone(), lines).await {
-        Ok(peer) => match peer {
-            Some(peer) => peer,
-            None => return Err(ServerError::GameFull),
-        },
-        Err(e) => return Err(e),
-    };
+    /// The first seat that is neither occupied nor held for a
+    /// disconnected Player.
+    fn free_seat(&self) -> Option<Player> {
+        if self.self_play.is_some() {
+            return None;
+        }
 
-    // if there's currently only one Peer connected, prompt them to wait
-    // until another Peer connects and the game can start
+        [Player::First, Player::Second].iter().copied().find(|player| {
+            !self.pending.contains_key(player) && !self.seats.values().any(|seat| seat == player)
+        })
+    }
 
-    // let everyone else know a new player has connected
-    {
-        let mut state = state.lock().await;
-        state
-            .broadcast(addr, &serde_json::to_string(&Response::GameStart)?)
-            .await;
+    /// Add the time since the current Player was prompted to their total
+    /// thinking time as of `now`, and start timing the next Turn.
+    fn record_thinking_time(&mut self, now: Instant) {
+        self.thinking_time[self.current_player.index()] += now - self.turn_started;
+        self.turn_started = now;
     }
 
-    // Process incoming messages until stream is exhausted by a disconnect
-    loop {
-        tokio::select! {
-            // A message was received from the other player. Send it to the current player.
-            Some(msg) = peer.rx.recv() => {
-                let mut state = state.lock().await;
+    /// Save the game to the database. The game is written inside a
+    /// transaction so that a failure never leaves a partial record behind,
+    /// and a resumed game is written over the game it was resumed from.
+    /// Does nothing when persistence is off.
+    pub fn persist(&mut self) -> Result<(), ServerError> {
+        let db_connection = match self.db_connection.as_mut() {
+            Some(db_connection) => db_connection,
+            None => return Ok(()),
+        };
+        let turns = serde_json::to_string(&self.turns)?;
+        let thinking_time = serde_json::to_string(&self.thinking_time)?;
+        let result = serde_json::to_string(&self.result)?;
+        // kept so the game can be replayed as it was played, e.g. with the
+        // symbols swapped
+        let ruleset = serde_json::to_string(&self.ruleset)?;
 
-                let turn: Turn = serde_json::from_str(&msg)?;
-                state.turns.push(turn);
+        // stored compactly so a game's result can be read without replaying
+        // it; left empty if the recorded Turns can't be replayed
+        let start = match &self.start_position {
+            Some(board) => board.clone(),
+            None => Board::new(&self.ruleset),
+        };
+        let final_board = start
+            .apply_turns(&self.turns)
+            .map(|board| board.encode())
+            .ok();
+        let start_position = self.start_position.as_ref().map(Board::encode);
+        let uuid = self.game_id.to_string();
 
-                peer.lines.send(serde_json::to_string(&Response::Turn(turn))?).await?;
-            }
+        let transaction = db_connection.transaction()?;
+        match self.resumed_from {
+            Some(id) => transaction.execute(
+                "UPDATE games SET turns = ?1, thinking_time = ?2, final_board = ?3, result = ?4, start_position = ?5, uuid = ?6, ruleset = ?7 WHERE id = ?8",
+                rusqlite::params![turns, thinking_time, final_board, result, start_position, uuid, ruleset, id],
+            )?,
+            None => transaction.execute(
+                "INSERT INTO games (turns, thinking_time, final_board, result, start_position, uuid, ruleset) values (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![turns, thinking_time, final_board, result, start_position, uuid, ruleset],
+            )?,
+        };
+        transaction.commit()?;
 
-            result = peer.lines.next() => match result {
-                // Message received from the current player.
-                // Broadcast it to the other player.
-                Some(Ok(msg)) => {
-                    let mut state = state.lock().await;
-                    let turn: Turn = serde_json::from_str(&msg)?;
+        Ok(())
+    }
 
-                    if turn.source == state.current_player {
-                        state.turns.push(turn);
+    /// Save the result of a finished match, won by `winner` or drawn if
+    /// that's `None`, to the database. Does nothing when persistence is off.
+    fn persist_match(&mut self, series: &Match, winner: Option<Player>) -> Result<(), ServerError> {
+        let db_connection = match self.db_connection.as_ref() {
+            Some(db_connection) => db_connection,
+            None => return Ok(()),
+        };
+        let scores = serde_json::to_string(&series.scores)?;
+        let winner = serde_json::to_string(&winner)?;
 
-                        state.broadcast(addr, &msg).await;
-                        state.back_to_sender(addr, &serde_json::to_string(&Response::Acknowledged)?).await;
+        db_connection.execute(
+            "INSERT INTO matches (games_to_win, scores, winner) values (?1, ?2, ?3)",
+            rusqlite::params![series.games_to_win, scores, winner],
+        )?;
 
-                        state.current_player = !state.current_player;
-                    } else {
-                        state.back_to_sender(addr, &serde_json::to_string(&Response::NotYourTurn)?).await;
-                    }
+        Ok(())
+    }
+
+    /// Count a game of a match that has just ended, then either start the
+    /// next game or tell everyone how the match ended. Each game but the last
+    /// is saved here, as the last one is saved with the server's state.
+    /// Does nothing outside of a match.
+    fn finish_game(&mut self, sender: ClientId) -> Result<(), ServerError> {
+        let (result, series) = match (self.result, self.series.as_mut()) {
+            (Some(result), Some(series)) => (result, series),
+            _ => return Ok(()),
+        };
+        let match_over = series.record(result);
+        let series = *series;
+
+        let msg = match match_over {
+            Some(winner) => {
+                let message = match winner {
+                    Some(winner) => format!("Player {} won the match.", winner.index() + 1),
+                    None => String::from("The match was drawn."),
+                };
+                self.log(Event::new("match_over", message));
+                if let Err(e) = self.persist_match(&series, winner) {
+                    self.log_error(Event::new(
+                        "save_failed",
+                        format!("Failed to persist match to database: {}", e),
+                    ));
                 }
-                // Some sort of error occurred
-                Some(Err(e)) => {
-                    let mut state = state.lock().await;
 
-                    let error_message = format!("An error occurred while processing messages from Player {}: {}", peer.number, e);
-                    eprintln!("{}", error_message);
+                Response::MatchOver {
+                    winner,
+                    scores: series.scores,
+                }
+            }
+            None => {
+                if let Err(e) = self.persist() {
+                    self.log_error(Event::new(
+                        "save_failed",
+                        format!("Failed to persist game to database: {}", e),
+                    ));
+                }
+                self.next_game(series.first_player);
 
-                    state.back_to_sender(addr, &serde_json::to_string(&Response::ServerError)?).await;
+                Response::NextGame {
+                    first_player: series.first_player,
+                    scores: series.scores,
                 }
-                // The stream has been exhausted
-                None => break,
             }
+        };
+
+        let msg = serde_json::to_string(&msg)?;
+        self.broadcast(sender, &msg);
+        self.back_to_sender(sender, &msg);
+        self.notify_spectators(&msg);
+
+        Ok(())
+    }
+
+    /// Clear the game away for the next game of a match, which the given
+    /// Player opens.
+    fn next_game(&mut self, first_player: Player) {
+        self.game += 1;
+        self.game_id = Uuid::new_v4();
+        self.resumed_from = None;
+        self.ruleset.first_player = first_player;
+        self.board = match &self.start_position {
+            Some(board) => board.clone(),
+            None => Board::new(&self.ruleset),
+        };
+        self.current_player = self.board.to_move(first_player).unwrap_or(first_player);
+        self.turns.clear();
+        self.placed.clear();
+        self.undo_requested_by = None;
+        self.result = None;
+        self.thinking_time = [Duration::ZERO; 2];
+        self.turn_started = Instant::now();
+        self.last_activity = Instant::now();
+    }
+
+    /// Send a line-encoded message to every spectator.
+    /// Spectators whose queues are full are dropped, like Players.
+    fn notify_spectators(&mut self, message: &str) {
+        self.spectators
+            .retain(|_, spectator| spectator.try_send(message.into()).is_ok());
+    }
+
+    /// Capture the game as it stands, for a client catching up on it.
+    pub fn snapshot(&self) -> Response {
+        Response::BoardState {
+            board: self.board.encode(),
+            display: self.board.to_string(),
+            current_player: self.current_player,
+            turns: self.turns.clone(),
         }
     }
 
-    // A player disconnected!
-    // Let the other player know.
-    {
-        let mut state = state.lock().await;
-        state.players.remove(&addr);
+    /// Describe the game for a client that isn't part of it.
+    pub fn status(&self) -> Response {
+        // a client holding both seats fills the second one too
+        let players = self.seats.len() + usize::from(self.self_play.is_some());
+        let seated = players + self.pending.len();
 
-        let msg = format!("Player {} has left the game.", peer.number);
-        println!("{}", msg);
+        Response::Status {
+            players,
+            spectators: self.spectators.len(),
+            in_progress: seated == 2,
+            board: (self.ruleset.height, self.ruleset.width),
+        }
+    }
 
-        state
-            .broadcast(addr, &serde_json::to_string(&Response::PlayerDisconnected)?)
-            .await;
+    /// Describe the game in full for debugging: the board, whose turn it
+    /// is, and who is connected.
+    pub fn dump(&self) -> String {
+        let mut dump = self.board.to_string();
+        let _ = writeln!(
+            dump,
+            "Turn {}, {:?} Player to move",
+            self.turns.len() + 1,
+            self.current_player
+        );
+
+        let mut seats = self.seats.iter().collect::<Vec<_>>();
+        seats.sort_by_key(|(id, _)| **id);
+        for (id, player) in seats {
+            let addr = self
+                .addrs
+                .get(id)
+                .map_or("an unknown address", String::as_str);
+            let _ = writeln!(
+                dump,
+                "Client {} from {} is seated as the {:?} Player",
+                id, addr, player
+            );
+        }
+
+        for player in self.pending.keys() {
+            let _ = writeln!(
+                dump,
+                "The {:?} Player's seat is held for them to rejoin",
+                player
+            );
+        }
+
+        let _ = write!(dump, "{} spectator(s) watching", self.spectators.len());
+
+        dump
     }
 
-    Ok(())
-}
+    /// Close the game if it has gone idle for longer than the idle timeout
+    /// as of `now`, telling every connected Player and spectator why.
+    /// Returns whether the game was closed.
+    pub fn reap_if_idle(&mut self, now: Instant) -> bool {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return false,
+        };
 
-/// Initialize a connection to the database.
-pub fn init_db() -> Result<Connection, ServerError> {
-    let connection = Connection::open(DB_PATH)?;
+        // an empty game has nothing to reap
+        if self.seats.is_empty() && self.pending.is_empty() {
+            return false;
+        }
 
-    if let Err(e) = connection.execute(
-        "CREATE TABLE games (
-            id INTEGER PRIMARY KEY,
-            turns TEXT NOT NULL
-        )",
-        [],
-    ) {
-        eprintln!("Database error: {}", e);
+        if now.saturating_duration_since(self.last_activity) < timeout {
+            return false;
+        }
+
+        if let Ok(msg) = serde_json::to_string(&Response::IdleTimeout) {
+            let ids = self.players.keys().copied().collect::<Vec<_>>();
+            for id in ids {
+                self.send_to(id, &msg);
+            }
+            self.notify_spectators(&msg);
+        }
+
+        // dropping the send handles ends each client's connection once it
+        // has been sent everything queued for it
+        self.players.clear();
+        self.seats.clear();
+        self.pending.clear();
+        self.self_play = None;
+        self.spectators.clear();
+
+        true
     }
 
-    Ok(connection)
+    /// Drop every reconnect token that has expired as of `now`. A seat
+    /// still held open for an expired token is freed, and everyone left in
+    /// the game is told its Player is gone. Returns how many tokens were
+    /// dropped.
+    pub fn sweep_expired_tokens(&mut self, now: Instant) -> usize {
+        let expired = self
+            .reconnect_tokens
+            .iter()
+            .filter(|(_, held)| held.is_expired(now))
+            .map(|(player, _)| *player)
+            .collect::<Vec<_>>();
+
+        for player in &expired {
+            self.reconnect_tokens.remove(player);
+
+            if self.pending.remove(player).is_none() {
+                continue;
+            }
+            if let Ok(msg) = serde_json::to_string(&Response::PlayerDisconnected) {
+                let ids = self.players.keys().copied().collect::<Vec<_>>();
+                for id in ids {
+                    self.send_to(id, &msg);
+                }
+                self.notify_spectators(&msg);
+            }
+        }
+
+        expired.len()
+    }
+
+    /// Ask the other Player to agree to take back the last exchange of
+    /// Turns. Only the Player to move may ask, once both have moved and
+    /// while the game is still going.
+    fn request_undo(&mut self, sender: ClientId, player: Player) -> Result<(), ServerError> {
+        if player != self.current_player
+            || self.placed.len() < 2
+            || self.undo_requested_by.is_some()
+            || self.result.is_some()
+        {
+            self.back_to_sender(sender, &serde_json::to_string(&Response::UndoDeclined)?);
+            return Ok(());
+        }
+
+        self.undo_requested_by = Some(player);
+        self.broadcast(sender, &serde_json::to_string(&Response::UndoRequested)?);
+
+        Ok(())
+    }
+
+    /// Settle the other Player's request to undo at `now`. If the answer is
+    /// yes, the last exchange of Turns is taken back, the move goes back to
+    /// whoever took the earlier of them, and everyone is told so. A request
+    /// still open when the game ended is turned down.
+    fn answer_undo(
+        &mut self,
+        sender: ClientId,
+        player: Player,
+        accept: bool,
+        now: Instant,
+    ) -> Result<(), ServerError> {
+        // a Player can't answer their own request
+        match self.undo_requested_by {
+            Some(requester) if requester != player => self.undo_requested_by = None,
+            _ => return Ok(()),
+        }
+
+        if !accept || self.result.is_some() {
+            let msg = serde_json::to_string(&Response::UndoDeclined)?;
+            self.broadcast(sender, &msg);
+            if self.result.is_some() {
+                self.back_to_sender(sender, &msg);
+            }
+            return Ok(());
+        }
+
+        // the time spent so far counts for the Player to move, and whoever
+        // moves next starts their Turn afresh
+        self.record_thinking_time(now);
+
+        let plies = 2;
+        for _ in 0..plies {
+            if let Some((row, col)) = self.placed.pop() {
+                self.board.clear(row, col);
+            }
+            if let Some(turn) = self.turns.pop() {
+                self.current_player = turn.source;
+            }
+        }
+        self.last_activity = now;
+
+        let msg = serde_json::to_string(&Response::Undone { plies })?;
+        self.broadcast(sender, &msg);
+        self.back_to_sender(sender, &msg);
+        self.notify_spectators(&msg);
+
+        Ok(())
+    }
+
+    /// Concede the game on behalf of the Peer's Player at `now`, telling
+    /// everyone it is over. A game that has already ended can't be resigned.
+    fn resign(&mut self, sender: ClientId, peer: &Peer, now: Instant) -> Result<(), ServerError> {
+        if self.result.is_some() {
+            return self.send_error(sender, ErrorCode::InvalidMove, "The game is already over.");
+        }
+
+        self.log(
+            Event::new("resign", format!("Player {} resigned.", peer.number))
+                .client(sender)
+                .player(peer.number),
+        );
+        self.record_thinking_time(now);
+        let result = GameResult::Resigned(peer.player);
+        self.result = Some(result);
+
+        let msg = serde_json::to_string(&Response::GameOver {
+            result,
+            thinking_time: Some(self.thinking_time),
+        })?;
+        self.broadcast(sender, &msg);
+        self.back_to_sender(sender, &msg);
+        self.notify_spectators(&msg);
+
+        self.finish_game(sender)
+    }
+
+    /// Play a Turn a Peer submitted at `now`, or tell them why it can't be
+    /// played. Nothing here awaits, so the game is never left partway
+    /// through recording a Turn.
+    fn take_turn(
+        &mut self,
+        sender: ClientId,
+        peer: &Peer,
+        turn: Turn,
+        now: Instant,
+    ) -> Result<(), ServerError> {
+        // A Peer may only submit Turns on behalf of the Player it was
+        // assigned when it joined.
+        if !peer.self_play && turn.source != peer.player {
+            let message = format!("You can only take Turns as Player {}.", peer.number);
+            return self.send_error(sender, ErrorCode::NotYourTurn, message);
+        }
+        // a Turn that arrives just after the game ended never makes it into
+        // the game's history
+        if self.result.is_some() {
+            return self.send_error(sender, ErrorCode::InvalidMove, "The game is already over.");
+        }
+        if turn.source != self.current_player {
+            return self.send_error(sender, ErrorCode::NotYourTurn, "It's not your turn yet.");
+        }
+
+        let (row, col) = match self.board.apply_turn(&turn) {
+            Ok(coordinate) => coordinate,
+            Err(e) => {
+                self.log_error(
+                    Event::new(
+                        "rejected_turn",
+                        format!("Rejected a Turn from Player {}: {}", peer.number, e),
+                    )
+                    .client(sender)
+                    .player(peer.number),
+                );
+                return self.send_error(sender, ErrorCode::InvalidMove, e.to_string());
+            }
+        };
+
+        let game_over = match self.board.winner_at(row, col) {
+            Some(winner) => Some(Some(winner)),
+            None if self.board.is_full() => Some(None),
+            // don't make the Players fill in a Board nobody can win on
+            None if !self.board.can_anyone_win() => {
+                let message = format!(
+                    "No Player can win anymore, declaring a tie with the board {:.0}% full",
+                    self.board.fill_ratio() * 100.0
+                );
+                self.log(Event::new("early_tie", message).client(sender));
+                Some(None)
+            }
+            None => None,
+        };
+        self.result = game_over.map(|winner| match winner {
+            Some(winner) => GameResult::Win(winner),
+            None => GameResult::Tie,
+        });
+
+        self.record_thinking_time(now);
+        self.last_activity = now;
+        self.turns.push(turn);
+        self.placed.push((row, col));
+        self.undo_requested_by = None;
+        self.current_player = match self.turn_policy {
+            TurnPolicy::Alternate => !self.current_player,
+            TurnPolicy::RandomEachTurn if self.rng.random_bool(0.5) => Player::First,
+            TurnPolicy::RandomEachTurn => Player::Second,
+        };
+
+        if self.verbose_turns {
+            let ply = self.turns.len();
+            let message = format!(
+                "game={} ply={} {} -> ({},{})",
+                self.game, ply, turn, row, col
+            );
+            self.log(
+                Event::new("turn", message)
+                    .client(sender)
+                    .player(peer.number),
+            );
+        }
+
+        let ack = Response::Acknowledged {
+            coordinate: (row, col),
+            game_over,
+            next_player: self.current_player,
+            board: Some(self.board.to_string()).filter(|_| peer.thin_client),
+        };
+
+        // the other Player can't work out who moves next for themselves
+        // unless the Players are strictly alternating
+        let relayed = match self.turn_policy {
+            TurnPolicy::Alternate => serde_json::to_string(&turn)?,
+            TurnPolicy::RandomEachTurn => serde_json::to_string(&Response::Played {
+                turn,
+                next_player: self.current_player,
+            })?,
+        };
+        self.broadcast(sender, &relayed);
+        self.notify_spectators(&serde_json::to_string(&Response::Turn(turn))?);
+        self.back_to_sender(sender, &serde_json::to_string(&ack)?);
+
+        self.finish_game(sender)
+    }
+
+    /// Tell the sender that what they asked for couldn't be done.
+    fn send_error(
+        &mut self,
+        sender: ClientId,
+        code: ErrorCode,
+        message: impl Into<String>,
+    ) -> Result<(), ServerError> {
+        let error = Response::Error {
+            code,
+            message: message.into(),
+        };
+        self.back_to_sender(sender, &serde_json::to_string(&error)?);
+
+        Ok(())
+    }
+
+    /// Send a line-encoded message back to the original sender.
+    fn back_to_sender(&mut self, sender: ClientId, message: &str) {
+        self.send_to(sender, message);
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        if self.db_connection.is_none() {
+            return;
+        }
+        if !self.persist_on_disconnect && (!self.started || self.turns.is_empty()) {
+            self.log(Event::new(
+                "skip_save",
+                "Not saving a game that was abandoned before it got going.",
+            ));
+            return;
+        }
+
+        self.log(Event::new("save", "Saving game to database..."));
+
+        if let Err(e) = self.persist() {
+            self.log_error(Event::new(
+                "save_failed",
+                format!("Failed to persist game to database: {}", e),
+            ));
+        }
+    }
+}
+
+/// The state of each connected peer.
+pub struct Peer {
+    /// The Player's number, starting at 1.
+    number: u32,
+    /// The Player this Peer was assigned when it joined.
+    player: Player,
+    /// Whether this Peer holds both seats and plays both sides.
+    self_play: bool,
+    /// Whether this Peer is a thin client that wants the rendered board
+    /// with every Turn.
+    thin_client: bool,
+    /// The Peer's receiver handle.
+    rx: Rx,
+    /// Receive messages from players as lines, without having to worry
+    /// about working at the raw byte level.
+    lines: Lines,
+}
+
+impl Peer {
+    /// Create a new `Peer` instance and notify the client.
+    ///
+    /// The client's first message decides which seat it takes: a `Join`
+    /// takes the first free seat, while a `Rejoin` reclaims a seat that
+    /// is being held for a disconnected Player. A `SelfPlay` takes both
+    /// seats, if the server allows it and both are free. A `ThinClient`
+    /// joins like a `Join`.
+    async fn new(
+        state: Arc<Mutex<Shared>>,
+        mut lines: Lines,
+        id: ClientId,
+        request: Request,
+    ) -> Result<Option<Self>, ServerError> {
+        let mut state = state.lock().await;
+        let (tx, rx) = mpsc::channel(state.channel_capacity);
+
+        let self_play = matches!(request, Request::SelfPlay);
+        if self_play && !state.allow_self_play {
+            let error = Response::Error {
+                code: ErrorCode::Forbidden,
+                message: String::from("This server doesn't allow self-play."),
+            };
+            lines.send(serde_json::to_string(&error)?).await?;
+            return Ok(None);
+        }
+
+        let rejoined = match &request {
+            // only the client holding the seat's token may reclaim it,
+            // and only until the token expires
+            Request::Rejoin { player, token }
+                if state.reconnect_tokens.get(player).is_some_and(|held| {
+                    held.token == *token && !held.is_expired(Instant::now())
+                }) =>
+            {
+                state.pending.remove(player).map(|_| *player)
+            }
+            _ => None,
+        };
+
+        let seat = if self_play {
+            state
+                .free_seat()
+                .filter(|_| state.seats.is_empty() && state.pending.is_empty())
+        } else {
+            rejoined.or_else(|| state.free_seat())
+        };
+
+        let player = match seat {
+            Some(player) => player,
+            None => {
+                let response = if state.started {
+                    Response::GameInProgress
+                } else {
+                    Response::GameFull
+                };
+                lines.send(serde_json::to_string(&response)?).await?;
+                return Ok(None);
+            }
+        };
+
+        state.players.insert(id, tx);
+        state.seats.insert(id, player);
+        if self_play {
+            state.self_play = Some(id);
+        }
+
+        // a fresh token each time the seat is taken, so that an old one
+        // can't be replayed
+        let reconnect_token = state
+            .reconnect_grace
+            .filter(|_| !self_play)
+            .map(|_| ReconnectToken::issue(state.token_ttl));
+        if let Some(token) = &reconnect_token {
+            state.reconnect_tokens.insert(player, token.clone());
+        }
+        let reconnect_token = reconnect_token.map(|held| held.token);
+
+        let ruleset = state.ruleset.clone();
+        lines
+            .send(serde_json::to_string(&Response::Welcome {
+                player,
+                ruleset,
+                protocol_version: PROTOCOL_VERSION,
+                protocol_minor_version: PROTOCOL_MINOR_VERSION,
+                server_name: state.server_name.clone(),
+                start_position: state.start_position.as_ref().map(Board::encode),
+                reconnect_token,
+                best_of: state.series.map(|series| series.games),
+            })?)
+            .await?;
+
+        // a Player who rejoins, or joins a game that's already under way
+        // such as a resumed one, has to catch up on what they missed
+        if rejoined.is_some() || !state.turns.is_empty() {
+            let snapshot = state.snapshot();
+            lines.send(serde_json::to_string(&snapshot)?).await?;
+        }
+
+        Ok(Some(Peer {
+            number: player.index() as u32 + 1,
+            player,
+            self_play,
+            thin_client: matches!(request, Request::ThinClient),
+            lines,
+            rx,
+        }))
+    }
+}
+
+/// Relay game updates to a spectator until it disconnects. Anything the
+/// spectator sends is ignored.
+async fn spectate(
+    state: Arc<Mutex<Shared>>,
+    mut lines: Lines,
+    id: ClientId,
+) -> Result<(), ServerError> {
+    let mut rx = {
+        let mut state = state.lock().await;
+
+        if let Some(limit) = state.spectator_limit {
+            if state.spectators.len() >= limit {
+                drop(state);
+                lines
+                    .send(serde_json::to_string(&Response::SpectatorFull)?)
+                    .await?;
+                return Ok(());
+            }
+        }
+
+        let (tx, rx) = mpsc::channel(state.channel_capacity);
+        state.spectators.insert(id, tx);
+
+        // catch the spectator up before relaying what happens next
+        let snapshot = state.snapshot();
+        drop(state);
+        lines.send(serde_json::to_string(&snapshot)?).await?;
+
+        rx
+    };
+
+    let result = loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Some(msg) => {
+                    if let Err(e) = lines.send(msg).await {
+                        break Err(e.into());
+                    }
+                }
+                // the spectator fell behind and was dropped
+                None => break Ok(()),
+            },
+
+            result = lines.next() => match result {
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => break Err(e.into()),
+                None => break Ok(()),
+            }
+        }
+    };
+
+    state.lock().await.spectators.remove(&id);
+
+    result
+}
+
+/// Relay game updates to a client connected to the observer port. Such
+/// clients are always spectators, whatever they send.
+pub async fn observe<S: Stream + 'static>(
+    state: Arc<Mutex<Shared>>,
+    stream: S,
+    id: ClientId,
+) -> Result<(), ServerError> {
+    let transcript = state.lock().await.transcript.clone();
+    let lines = frame(stream, id, transcript);
+    spectate(state, lines, id).await
+}
+
+/// Process an individual client. Returns how the game ended for the client,
+/// or `None` if it never played or its game was closed for going idle.
+pub async fn process<S: Stream + 'static>(
+    state: Arc<Mutex<Shared>>,
+    stream: S,
+    id: ClientId,
+) -> Result<Option<GameResult>, ServerError> {
+    let transcript = state.lock().await.transcript.clone();
+    let mut lines = frame(stream, id, transcript);
+
+    // the client's first message says what it wants from the server
+    let request = match lines.next().await {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => return Ok(None),
+    };
+
+    match request {
+        Request::Status => {
+            let status = state.lock().await.status();
+            lines.send(serde_json::to_string(&status)?).await?;
+            return Ok(None);
+        }
+        Request::Spectate => return spectate(state, lines, id).await.map(|_| None),
+        _ => {}
+    }
+
+    let mut peer = match Peer::new(state.clone(), lines, id, request).await? {
+        Some(peer) => peer,
+        // the client has already been told the game is full or under
+        // way; turning it away is expected, not an error
+        None => {
+            let state = state.lock().await;
+            let reason = if state.started {
+                ServerError::GameInProgress
+            } else {
+                ServerError::GameFull
+            };
+            state.log(
+                Event::new(
+                    "turned_away",
+                    format!("Turned away client {}: {}", id, reason),
+                )
+                .client(id),
+            );
+
+            return Ok(None);
+        }
+    };
+
+    {
+        let state = state.lock().await;
+        let message = format!("Player {} joined game {}.", peer.number, state.game_id);
+        state.log(Event::new("join", message).client(id).player(peer.number));
+    }
+
+    // if there's currently only one Peer connected, prompt them to wait
+    // until another Peer connects and the game can start
+
+    // let everyone else know a new player has connected
+    {
+        let mut state = state.lock().await;
+        let msg = serde_json::to_string(&Response::GameStart)?;
+        state.broadcast(id, &msg);
+        state.notify_spectators(&msg);
+
+        state.last_activity = Instant::now();
+
+        // the Player who fills the last seat, or holds both, hears that the
+        // game can start too
+        if peer.self_play || state.seats.len() == 2 {
+            state.back_to_sender(id, &msg);
+        }
+
+        // the first Player's clock starts once both Players are present, and
+        // keeps running through a rejoin
+        if (state.seats.len() == 2 || peer.self_play) && !state.started {
+            state.started = true;
+            state.turn_started = Instant::now();
+        }
+    }
+
+    // whether the other Player left before this one did
+    let mut opponent_left = false;
+
+    // the seat is cleaned up below however the connection ends, even if
+    // the Player sent something the server couldn't handle
+    let relayed = relay(&state, &mut peer, id, &mut opponent_left).await;
+
+    // A player disconnected!
+    // Let the other player know, unless their seat is held open for them.
+    let result;
+    {
+        let mut state_guard = state.lock().await;
+        state_guard.players.remove(&id);
+        result = state_guard.result;
+
+        // the seat is already gone if the game was closed out from under
+        // this Player, and everyone has been told why
+        if state_guard.seats.remove(&id).is_none() {
+            return relayed.map(|_| None);
+        }
+        if peer.self_play {
+            state_guard.self_play = None;
+        }
+
+        state_guard.log(
+            Event::new("leave", format!("Player {} has left the game.", peer.number))
+                .client(id)
+                .player(peer.number),
+        );
+
+        // a Player holding both seats has no one to hand the game back to
+        match state_guard.reconnect_grace.filter(|_| !peer.self_play) {
+            Some(grace) => {
+                let player = peer.player;
+                let left_at = Instant::now();
+                state_guard.pending.insert(player, left_at);
+
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    tokio::time::sleep(grace).await;
+
+                    let mut state = state.lock().await;
+
+                    // the seat may have been reclaimed, possibly by a Player
+                    // who has since left again
+                    if state.pending.get(&player) == Some(&left_at) {
+                        state.pending.remove(&player);
+                        state.reconnect_tokens.remove(&player);
+
+                        if let Ok(msg) = serde_json::to_string(&Response::PlayerDisconnected) {
+                            state.broadcast(id, &msg);
+                            state.notify_spectators(&msg);
+                        }
+                    }
+                });
+            }
+            None => {
+                let msg = serde_json::to_string(&Response::PlayerDisconnected)?;
+                state_guard.broadcast(id, &msg);
+                state_guard.notify_spectators(&msg);
+            }
+        }
+    }
+
+    relayed?;
+
+    Ok(Some(match result {
+        Some(result) => result,
+        None if opponent_left => GameResult::OpponentLeft,
+        None => GameResult::LocalQuit,
+    }))
+}
+
+/// Pass messages between a seated Peer and the game until the Peer
+/// disconnects, noting in `opponent_left` whether the other Player left
+/// first.
+async fn relay(
+    state: &Arc<Mutex<Shared>>,
+    peer: &mut Peer,
+    id: ClientId,
+    opponent_left: &mut bool,
+) -> Result<(), ServerError> {
+    // Process incoming messages until stream is exhausted by a disconnect
+    loop {
+        // Both branches wait on futures that are safe to cancel, so the
+        // one that loses the race never drops a message: an unread line
+        // stays in `peer.lines` and an unread relay stays in `peer.rx`.
+        // Each branch then makes all its changes to the game under a single
+        // lock, without awaiting in between.
+        tokio::select! {
+            // pass on everything the other Player has already done before
+            // reading this Player's next message, so a Turn they submit at
+            // nearly the same time is checked against the latest game
+            biased;
+
+            // A message was received from the other player. Send it to the current player.
+            msg = peer.rx.recv() => match msg {
+                // the other Player's Turn was already recorded when they took
+                // it, so it's only passed on here, and only if it really is
+                // the other Player's: this Player's own Turns were already
+                // acknowledged
+                Some(msg) => match serde_json::from_str::<Turn>(&msg) {
+                    Ok(turn) if turn.source == peer.player => {}
+                    Ok(turn) => {
+                        peer.lines.send(serde_json::to_string(&Response::Turn(turn))?).await?;
+
+                        // thin clients are shown the board the Turn led to
+                        if peer.thin_client {
+                            let snapshot = state.lock().await.snapshot();
+                            peer.lines.send(serde_json::to_string(&snapshot)?).await?;
+                        }
+                    }
+                    // anything else is already a Response meant for this Player
+                    Err(_) => {
+                        let response = serde_json::from_str(&msg);
+                        if let Ok(Response::PlayerDisconnected) = response {
+                            *opponent_left = true;
+                        }
+
+                        peer.lines.send(msg).await?;
+
+                        if peer.thin_client && matches!(response, Ok(Response::Played { .. })) {
+                            let snapshot = state.lock().await.snapshot();
+                            peer.lines.send(serde_json::to_string(&snapshot)?).await?;
+                        }
+                    }
+                },
+                // this Player fell behind and was dropped
+                None => break,
+            },
+
+            result = peer.lines.next() => match result {
+                // Message received from the current player.
+                // Broadcast it to the other player.
+                Some(Ok(msg)) => {
+                    let mut state = state.lock().await;
+
+                    match serde_json::from_str::<Request>(&msg)? {
+                        Request::Turn(turn) => state.take_turn(id, peer, turn, Instant::now())?,
+                        Request::ThinClient => peer.thin_client = true,
+                        Request::Undo => state.request_undo(id, peer.player)?,
+                        Request::UndoReply { accept } => {
+                            state.answer_undo(id, peer.player, accept, Instant::now())?
+                        }
+                        Request::Resign => state.resign(id, peer, Instant::now())?,
+                        // joining or watching only makes sense as a client's
+                        // first message
+                        Request::Join
+                        | Request::Rejoin { .. }
+                        | Request::Spectate
+                        | Request::Status
+                        | Request::SelfPlay => {
+                            state.send_error(id, ErrorCode::Forbidden, "You've already joined the game.")?;
+                        }
+                    }
+                }
+                // Some sort of error occurred
+                Some(Err(e)) => {
+                    let mut state = state.lock().await;
+
+                    let error_message = format!("An error occurred while processing messages from Player {}: {}", peer.number, e);
+                    state.log_error(Event::new("error", error_message).client(id).player(peer.number));
+
+                    state.send_error(id, ErrorCode::Internal, "The server couldn't read your last message.")?;
+                }
+                // The stream has been exhausted
+                None => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically close the game if it has gone idle. Runs until the
+/// server shuts down.
+pub async fn reap_idle(state: Arc<Mutex<Shared>>) {
+    let mut ticks = tokio::time::interval(REAP_INTERVAL);
+
+    loop {
+        ticks.tick().await;
+
+        let mut state = state.lock().await;
+        if state.reap_if_idle(Instant::now()) {
+            state.log(Event::new("idle_timeout", "Closed the game after it went idle."));
+        }
+    }
+}
+
+/// Periodically drop expired reconnect tokens, freeing any seats held for
+/// them. Runs until the server shuts down.
+pub async fn sweep_tokens(state: Arc<Mutex<Shared>>) {
+    let mut ticks = tokio::time::interval(REAP_INTERVAL);
+
+    loop {
+        ticks.tick().await;
+
+        let mut state = state.lock().await;
+        let swept = state.sweep_expired_tokens(Instant::now());
+        if swept > 0 {
+            state.log(Event::new(
+                "token_expired",
+                format!("Dropped {} expired reconnect token(s).", swept),
+            ));
+        }
+    }
+}
+
+/// Log a dump of the game whenever the server receives `SIGUSR1`. Runs
+/// until the server shuts down.
+pub async fn dump_on_signal(state: Arc<Mutex<Shared>>) {
+    let mut signals = match signal(SignalKind::user_defined1()) {
+        Ok(signals) => signals,
+        Err(e) => {
+            state.lock().await.log_error(Event::new(
+                "error",
+                format!("Couldn't listen for SIGUSR1: {}", e),
+            ));
+            return;
+        }
+    };
+
+    while signals.recv().await.is_some() {
+        let state = state.lock().await;
+        state.log(Event::new("dump", state.dump()));
+    }
+}
+
+/// Answer every connection to the health check port with `OK`, without
+/// touching the game's state, so a probe succeeds for as long as the
+/// server is running.
+pub async fn answer_health_checks(listener: TcpListener) {
+    while let Ok((mut stream, _)) = listener.accept().await {
+        tokio::spawn(async move {
+            let _ = stream.write_all(b"OK\n").await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+/// Run a server for the given Params, accepting and serving clients until
+/// `shutdown` fires or its sender is dropped.
+pub async fn run_server(
+    params: Params,
+    mut shutdown: oneshot::Receiver<()>,
+) -> Result<(), ServerError> {
+    let state = Arc::new(Mutex::new(Shared::try_new(&params)?));
+    let connections = Connections {
+        live: Arc::new(AtomicUsize::new(0)),
+        max: params.max_connections,
+    };
+    let (logger, idle_timeout, token_ttl) = {
+        let state = state.lock().await;
+        (state.logger, state.idle_timeout, state.token_ttl)
+    };
+
+    // tasks that run alongside the main listener, stopped on shutdown
+    let mut background = vec![tokio::spawn(dump_on_signal(Arc::clone(&state)))];
+
+    if idle_timeout.is_some() {
+        background.push(tokio::spawn(reap_idle(Arc::clone(&state))));
+    }
+
+    if token_ttl.is_some() {
+        background.push(tokio::spawn(sweep_tokens(Arc::clone(&state))));
+    }
+
+    if let Some(health_addr) = params.health_addr {
+        let listener = TcpListener::bind(&health_addr).await?;
+
+        logger.info(
+            Event::new(
+                "listening",
+                format!("Health checks may connect on {}", listener.local_addr()?),
+            )
+            .addr(health_addr.to_string()),
+        );
+
+        background.push(tokio::spawn(answer_health_checks(listener)));
+    }
+
+    if let Some(observer_addr) = params.observer_addr {
+        let listener = TcpListener::bind(&observer_addr).await?;
+
+        logger.info(
+            Event::new(
+                "listening",
+                format!("Observers may connect on {}", listener.local_addr()?),
+            )
+            .addr(observer_addr.to_string()),
+        );
+
+        let state = Arc::clone(&state);
+        let connections = connections.clone();
+        background.push(tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        logger.error(Event::new(
+                            "error",
+                            format!("Failed to accept an observer: {}", e),
+                        ));
+                        continue;
+                    }
+                };
+                let id = next_id();
+
+                let message = format!("Got an observer connection from {}", addr);
+                connected(&state, id, &addr.to_string(), message).await;
+                serve(Arc::clone(&state), stream, id, true, connections.clone());
+            }
+        }));
+    }
+
+    let result = match params.unix_socket {
+        Some(path) => {
+            let listener = UnixListener::bind(&path)?;
+            let addr = path.display().to_string();
+
+            logger.info(
+                Event::new("listening", format!("Server running on {}", addr)).addr(addr.as_str()),
+            );
+
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => break Err(e.into()),
+                    },
+                    _ = &mut shutdown => break Ok(()),
+                };
+                let id = next_id();
+
+                connected(&state, id, &addr, String::from("Got a connection")).await;
+                serve(Arc::clone(&state), stream, id, false, connections.clone());
+            }
+        }
+        None => {
+            let listener = TcpListener::bind(&params.addr).await?;
+            let local_addr = listener.local_addr()?;
+
+            logger.info(
+                Event::new("listening", format!("Server running on {}", local_addr))
+                    .addr(local_addr.to_string()),
+            );
+
+            loop {
+                let (stream, addr) = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok(connection) => connection,
+                        Err(e) => break Err(e.into()),
+                    },
+                    _ = &mut shutdown => break Ok(()),
+                };
+                let id = next_id();
+
+                let message = format!("Got a connection from {}", addr);
+                connected(&state, id, &addr.to_string(), message).await;
+                serve(Arc::clone(&state), stream, id, false, connections.clone());
+            }
+        }
+    };
+
+    // wait for each task to actually stop, so that none of them keeps the
+    // game alive, and unsaved, once the server has returned
+    for task in background {
+        task.abort();
+        let _ = task.await;
+    }
+
+    result
+}
+
+/// Assign a new client id.
+fn next_id() -> ClientId {
+    LAST_ID.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// Remember where a client connected from, give its connection an id, and
+/// log its arrival.
+async fn connected(state: &Arc<Mutex<Shared>>, id: ClientId, addr: &str, message: String) {
+    let mut state = state.lock().await;
+
+    state.addrs.insert(id, addr.to_string());
+    state.connections.insert(id, Uuid::new_v4());
+    state.log(Event::new("connect", message).client(id));
+}
+
+/// Counts the clients connected at once, across every listener, against
+/// the most the server allows.
+#[derive(Debug, Clone)]
+struct Connections {
+    live: Arc<AtomicUsize>,
+    max: Option<usize>,
+}
+
+impl Connections {
+    /// Count a new client, unless the server is already at its limit.
+    fn try_claim(&self) -> bool {
+        let max = self.max.unwrap_or(usize::MAX);
+
+        self.live
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |live| {
+                (live < max).then(|| live + 1)
+            })
+            .is_ok()
+    }
+
+    /// Stop counting a client that has disconnected.
+    fn release(&self) {
+        self.live.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Turn a client away because the server already has as many connections
+/// as it allows.
+async fn turn_away<S: Stream + 'static>(
+    stream: S,
+    id: ClientId,
+    transcript: Option<Transcript>,
+) -> Result<(), ServerError> {
+    let mut lines = frame(stream, id, transcript);
+    let response = Response::Error {
+        code: ErrorCode::ServerFull,
+        message: String::from("The server is full. Try again later."),
+    };
+
+    lines.send(serde_json::to_string(&response)?).await?;
+    Ok(())
+}
+
+/// Process a client on its own task. Clients of the observer port are
+/// only ever shown the game.
+fn serve<S: Stream + 'static>(
+    state: Arc<Mutex<Shared>>,
+    stream: S,
+    id: ClientId,
+    observer: bool,
+    connections: Connections,
+) {
+    tokio::spawn(async move {
+        if !connections.try_claim() {
+            let mut state = state.lock().await;
+            state.addrs.remove(&id);
+            state.connections.remove(&id);
+            state.log(
+                Event::new(
+                    "turned_away",
+                    format!("Turned away client {}: the server is full", id),
+                )
+                .client(id),
+            );
+            let transcript = state.transcript.clone();
+            drop(state);
+
+            let _ = turn_away(stream, id, transcript).await;
+            return;
+        }
+
+        let result = if observer {
+            observe(Arc::clone(&state), stream, id).await.map(|_| None)
+        } else {
+            process(Arc::clone(&state), stream, id).await
+        };
+
+        match result {
+            Ok(Some(result)) => state.lock().await.log(
+                Event::new("result", format!("Client {} finished: {:?}", id, result)).client(id),
+            ),
+            Ok(None) => {}
+            Err(e) => state
+                .lock()
+                .await
+                .log_error(Event::new("error", format!("Error: {}", e)).client(id)),
+        }
+
+        let mut state = state.lock().await;
+        state.addrs.remove(&id);
+        state.connections.remove(&id);
+        drop(state);
+        connections.release();
+    });
+}
+
+/// Initialize a connection to the database.
+pub fn init_db() -> Result<Connection, ServerError> {
+    let connection = Connection::open(DB_PATH)?;
+    create_tables(&connection);
+
+    Ok(connection)
+}
+
+/// Create the tables games are saved in, bringing any that already exist
+/// up to date.
+pub fn create_tables(connection: &Connection) {
+    if let Err(e) = connection.execute(
+        "CREATE TABLE games (
+            id INTEGER PRIMARY KEY,
+            turns TEXT NOT NULL
+        )",
+        [],
+    ) {
+        eprintln!("Database error: {}", e);
+    }
+
+    for migration in MIGRATIONS {
+        let _ = connection.execute(migration, []);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared() -> Shared {
+        Shared::try_new(&Params::from_iter(&["server", "--no-persist", "--quiet"])).unwrap()
+    }
+
+    /// A Peer seated as the given Player, connected to nothing.
+    fn peer(player: Player) -> Peer {
+        let (_, rx) = mpsc::channel(1);
+        let (stream, _) = tokio::io::duplex(64);
+
+        Peer {
+            number: player.index() as u32 + 1,
+            player,
+            self_play: false,
+            thin_client: false,
+            rx,
+            lines: frame(stream, 0, None),
+        }
+    }
+
+    fn turn(source: Player, row: usize) -> Turn {
+        Turn {
+            source,
+            mov: Move {
+                side: Side::Left,
+                row,
+            },
+        }
+    }
+
+    #[test]
+    fn board_over_the_cell_cap_is_refused() {
+        let params = |size: &str| {
+            Params::from_iter(&[
+                "server",
+                "--no-persist",
+                "--quiet",
+                "--height",
+                size,
+                "--width",
+                size,
+                "--max-cells",
+                "100",
+            ])
+        };
+
+        assert!(matches!(
+            Shared::try_new(&params("100000")),
+            Err(ServerError::BoardTooLarge {
+                height: 100000,
+                width: 100000,
+                max_cells: 100
+            })
+        ));
+        assert!(matches!(
+            Shared::try_new(&params("11")),
+            Err(ServerError::BoardTooLarge { .. })
+        ));
+        assert_eq!(Shared::try_new(&params("10")).unwrap().board.height, 10);
+    }
+
+    #[tokio::test]
+    async fn sweep_drops_expired_tokens_and_frees_their_seats() {
+        let mut state = shared();
+        let (tx, mut rx) = mpsc::channel(4);
+        state.players.insert(2, tx);
+        state.seats.insert(2, Player::Second);
+        state.pending.insert(Player::First, Instant::now());
+        state.reconnect_tokens.insert(
+            Player::First,
+            ReconnectToken::issue(Some(Duration::from_secs(10))),
+        );
+        state.reconnect_tokens.insert(
+            Player::Second,
+            ReconnectToken::issue(Some(Duration::from_secs(60))),
+        );
+        let later = Instant::now() + Duration::from_secs(30);
+
+        assert_eq!(state.sweep_expired_tokens(Instant::now()), 0);
+        assert_eq!(state.sweep_expired_tokens(later), 1);
+
+        assert!(!state.reconnect_tokens.contains_key(&Player::First));
+        assert!(state.reconnect_tokens.contains_key(&Player::Second));
+        assert!(state.pending.is_empty());
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            serde_json::to_string(&Response::PlayerDisconnected).unwrap()
+        );
+        assert_eq!(state.free_seat(), Some(Player::First));
+    }
+
+    #[test]
+    fn tokens_are_random() {
+        let (first, second) = (ReconnectToken::issue(None), ReconnectToken::issue(None));
+
+        assert_ne!(first.token, second.token);
+        assert_eq!(first.token.len(), 32);
+        assert!(!first.is_expired(Instant::now() + Duration::from_secs(1_000_000)));
+    }
+
+    #[test]
+    fn persist_saves_the_game() {
+        let mut state = shared();
+        let connection = Connection::open_in_memory().unwrap();
+        create_tables(&connection);
+        state.db_connection = Some(connection);
+        state.turns.push(turn(Player::First, 0));
+
+        state.persist().unwrap();
+
+        let turns: String = state
+            .db_connection
+            .as_ref()
+            .unwrap()
+            .query_row("SELECT turns FROM games", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(turns, serde_json::to_string(&state.turns).unwrap());
+    }
+
+    #[test]
+    fn persist_saves_the_ruleset() {
+        let mut state = shared();
+        let connection = Connection::open_in_memory().unwrap();
+        create_tables(&connection);
+        state.db_connection = Some(connection);
+        state.ruleset.swap_symbols = true;
+        state.ruleset.height = 9;
+        state.turns.push(turn(Player::First, 8));
+
+        state.persist().unwrap();
+
+        let connection = state.db_connection.as_ref().unwrap();
+        let ruleset: String = connection
+            .query_row("SELECT ruleset FROM games", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Ruleset>(&ruleset).unwrap(),
+            state.ruleset
+        );
+        // the game is replayed under its own rules, not the standard ones
+        let (checked, problems) = verify::verify_games(connection, &Ruleset::standard()).unwrap();
+        assert_eq!((checked, problems), (1, Vec::new()));
+    }
+
+    #[test]
+    fn resumed_game_awaits_its_sixth_move() {
+        let mut state = shared();
+        let connection = Connection::open_in_memory().unwrap();
+        create_tables(&connection);
+        let turns = (0..5)
+            .map(|row| {
+                turn(
+                    if row % 2 == 0 {
+                        Player::First
+                    } else {
+                        Player::Second
+                    },
+                    row,
+                )
+            })
+            .collect::<Vec<_>>();
+        let thinking_time = [Duration::from_secs(4), Duration::from_secs(2)];
+        connection
+            .execute(
+                "INSERT INTO games (turns, thinking_time, result) values (?1, ?2, 'null')",
+                rusqlite::params![
+                    serde_json::to_string(&turns).unwrap(),
+                    serde_json::to_string(&thinking_time).unwrap()
+                ],
+            )
+            .unwrap();
+        state.db_connection = Some(connection);
+
+        state.resume(1).unwrap();
+
+        assert_eq!(
+            state.board.encode(),
+            Board::from_turns(&state.ruleset, &turns).unwrap().encode()
+        );
+        assert_eq!(state.current_player, Player::Second);
+        assert_eq!(state.turns, turns);
+        assert_eq!(state.placed.len(), 5);
+        assert_eq!(state.thinking_time, thinking_time);
+
+        // the Turns from before the game was resumed can be taken back
+        state.request_undo(2, Player::Second).unwrap();
+        assert_eq!(state.undo_requested_by, Some(Player::Second));
+
+        state
+            .take_turn(
+                2,
+                &peer(Player::Second),
+                turn(Player::Second, 5),
+                Instant::now(),
+            )
+            .unwrap();
+        assert_eq!(state.turns.len(), 6);
+        state.persist().unwrap();
+
+        // the game is saved over the one it was resumed from
+        let connection = state.db_connection.as_ref().unwrap();
+        let (count, saved): (i64, String) = connection
+            .query_row("SELECT COUNT(*), turns FROM games", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(saved, serde_json::to_string(&state.turns).unwrap());
+    }
+
+    #[test]
+    fn resumed_game_is_played_under_its_own_rules() {
+        let mut state = shared();
+        let connection = Connection::open_in_memory().unwrap();
+        create_tables(&connection);
+        let ruleset = Ruleset {
+            height: 4,
+            width: 5,
+            win_length: 3,
+            side_restrictions: vec![(1, Side::Right)].into_iter().collect(),
+            ..Ruleset::standard()
+        };
+        let turns = vec![turn(Player::First, 0), turn(Player::Second, 2)];
+        connection
+            .execute(
+                "INSERT INTO games (turns, ruleset) values (?1, ?2)",
+                rusqlite::params![
+                    serde_json::to_string(&turns).unwrap(),
+                    serde_json::to_string(&ruleset).unwrap()
+                ],
+            )
+            .unwrap();
+        state.db_connection = Some(connection);
+
+        state.resume(1).unwrap();
+
+        assert_eq!(state.ruleset, ruleset);
+        assert_eq!((state.board.height, state.board.width), (4, 5));
+        assert_eq!(state.board.encode(), "____X/_____/____O/_____");
+        // the saved restriction holds, so Row 1 can't be played from the left
+        state
+            .take_turn(
+                1,
+                &peer(Player::First),
+                turn(Player::First, 1),
+                Instant::now(),
+            )
+            .unwrap();
+        assert_eq!(state.turns.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn undo_hands_the_move_back_to_whoever_took_the_earlier_turn() {
+        let mut state = shared();
+        let (tx, mut rx) = mpsc::channel(8);
+        state.players.insert(1, tx);
+        let first = peer(Player::First);
+        let now = Instant::now();
+
+        // under a random turn order, the same Player may move twice in a row
+        state
+            .take_turn(1, &first, turn(Player::First, 0), now)
+            .unwrap();
+        state.current_player = Player::First;
+        state
+            .take_turn(1, &first, turn(Player::First, 1), now)
+            .unwrap();
+        state.current_player = Player::Second;
+
+        state.request_undo(2, Player::Second).unwrap();
+        state.answer_undo(1, Player::First, true, now).unwrap();
+
+        assert!(state.turns.is_empty());
+        assert_eq!(state.board.count(Player::First), 0);
+        assert_eq!(state.current_player, Player::First);
+        // skip the acknowledgements of the Turns and the request itself
+        for _ in 0..3 {
+            rx.recv().await.unwrap();
+        }
+        assert_eq!(
+            serde_json::from_str::<Response>(&rx.recv().await.unwrap()).unwrap(),
+            Response::Undone { plies: 2 }
+        );
+    }
+
+    #[tokio::test]
+    async fn finished_game_cant_be_undone() {
+        let mut state = shared();
+        let (tx, mut rx) = mpsc::channel(8);
+        state.players.insert(1, tx);
+        let now = Instant::now();
+        state
+            .take_turn(1, &peer(Player::First), turn(Player::First, 0), now)
+            .unwrap();
+        state
+            .take_turn(2, &peer(Player::Second), turn(Player::Second, 1), now)
+            .unwrap();
+        state.result = Some(GameResult::Win(Player::Second));
+        // skip the acknowledgement of the first Turn and the relay of the
+        // second
+        rx.recv().await.unwrap();
+        rx.recv().await.unwrap();
+        let declined = serde_json::to_string(&Response::UndoDeclined).unwrap();
+
+        state.request_undo(1, Player::First).unwrap();
+        assert_eq!(rx.recv().await.unwrap(), declined);
+        assert_eq!(state.undo_requested_by, None);
+
+        // nor can a request made before the game ended be agreed to after
+        state.undo_requested_by = Some(Player::First);
+        state.answer_undo(2, Player::Second, true, now).unwrap();
+        assert_eq!(rx.recv().await.unwrap(), declined);
+        assert_eq!(state.turns.len(), 2);
+    }
+
+    #[test]
+    fn failed_persist_is_an_error_not_a_panic() {
+        let mut state = shared();
+        // without its tables, every insert into the database fails
+        state.db_connection = Some(Connection::open_in_memory().unwrap());
+        state.turns.push(turn(Player::First, 0));
+
+        assert!(matches!(
+            state.persist(),
+            Err(ServerError::DatabaseError { .. })
+        ));
+    }
+
+    #[test]
+    fn dump_shows_the_board_and_whose_turn_it_is() {
+        let mut state = shared();
+        let first = turn(Player::First, 0);
+        state.board.apply_turn(&first).unwrap();
+        state.turns.push(first);
+        state.current_player = Player::Second;
+        state.seats.insert(1, Player::First);
+        state.addrs.insert(1, String::from("127.0.0.1:4000"));
+        state.pending.insert(Player::Second, Instant::now());
+
+        let dump = state.dump();
+
+        assert!(dump.starts_with(&state.board.to_string()));
+        assert!(dump.contains("0 [ _ _ _ _ _ _ X ]\n"));
+        assert!(dump.contains("Turn 2, Second Player to move"));
+        assert!(dump.contains("Client 1 from 127.0.0.1:4000 is seated as the First Player"));
+        assert!(dump.contains("The Second Player's seat is held for them to rejoin"));
+        assert!(dump.ends_with("0 spectator(s) watching"));
+    }
+
+    /// Drop the game with a database of its own, and count the games that
+    /// were saved to it.
+    fn games_saved_on_drop(mut state: Shared) -> i64 {
+        let path = std::env::temp_dir().join(format!("sidestacker-{}.db", state.game_id));
+        let connection = Connection::open(&path).unwrap();
+        create_tables(&connection);
+        state.db_connection = Some(connection);
+        drop(state);
+
+        let count = Connection::open(&path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM games", [], |row| row.get(0))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        count
+    }
+
+    #[test]
+    fn abandoned_game_is_not_saved() {
+        assert_eq!(games_saved_on_drop(shared()), 0);
+    }
+
+    #[test]
+    fn played_game_is_saved() {
+        let mut state = shared();
+        state.started = true;
+        state.turns.push(turn(Player::First, 0));
+
+        assert_eq!(games_saved_on_drop(state), 1);
+    }
+
+    #[tokio::test]
+    async fn player_who_falls_behind_is_dropped() {
+        let mut state = shared();
+        let (tx, mut rx) = mpsc::channel(1);
+        state.players.insert(1, tx);
+
+        state.send_to(1, "first");
+        assert!(state.players.contains_key(&1));
+        state.send_to(1, "second");
+        assert!(!state.players.contains_key(&1));
+
+        // what was queued before the Player was dropped still arrives, and
+        // then the connection ends
+        assert_eq!(rx.recv().await.as_deref(), Some("first"));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn only_an_idle_game_is_reaped() {
+        let params = Params::from_iter(&[
+            "server",
+            "--no-persist",
+            "--quiet",
+            "--idle-timeout-secs",
+            "60",
+        ]);
+        let mut state = Shared::try_new(&params).unwrap();
+        let (tx, mut rx) = mpsc::channel(4);
+        state.players.insert(1, tx);
+        state.seats.insert(1, Player::First);
+        let start = Instant::now();
+        state.last_activity = start;
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(!state.reap_if_idle(at(59)));
+
+        // taking a Turn keeps the game alive for another full timeout
+        let first = peer(Player::First);
+        state
+            .take_turn(1, &first, turn(Player::First, 0), at(59))
+            .unwrap();
+        assert!(!state.reap_if_idle(at(118)));
+        assert!(state.reap_if_idle(at(119)));
+        assert!(state.seats.is_empty());
+
+        // skip the acknowledgement of the Turn
+        rx.recv().await.unwrap();
+        let timed_out: Response = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(timed_out, Response::IdleTimeout);
+    }
+
+    #[test]
+    fn quiet_flag_quiets_the_logger() {
+        assert!(shared().logger.quiet);
+
+        let params = Params::from_iter(&["server", "--no-persist"]);
+        assert!(!Shared::try_new(&params).unwrap().logger.quiet);
+    }
+
+    #[tokio::test]
+    async fn thinking_time_adds_up_each_players_delays() {
+        let mut state = shared();
+        let start = Instant::now();
+        state.turn_started = start;
+
+        let (first, second) = (peer(Player::First), peer(Player::Second));
+        let at = |secs| start + Duration::from_secs(secs);
+        state
+            .take_turn(1, &first, turn(Player::First, 0), at(3))
+            .unwrap();
+        state
+            .take_turn(2, &second, turn(Player::Second, 1), at(8))
+            .unwrap();
+        state
+            .take_turn(1, &first, turn(Player::First, 2), at(10))
+            .unwrap();
+
+        assert_eq!(state.thinking_time, [Duration::from_secs(5); 2]);
+    }
+
+    #[tokio::test]
+    async fn game_over_reports_thinking_time() {
+        let mut state = shared();
+        let (tx, mut rx) = mpsc::channel(4);
+        state.players.insert(1, tx);
+        let start = Instant::now();
+        state.turn_started = start;
+
+        let at = |secs| start + Duration::from_secs(secs);
+        let first = peer(Player::First);
+        state
+            .take_turn(1, &first, turn(Player::First, 0), at(2))
+            .unwrap();
+        state.resign(1, &peer(Player::Second), at(9)).unwrap();
+
+        // skip the acknowledgement of the Turn
+        rx.recv().await.unwrap();
+        let game_over: Response = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(
+            game_over,
+            Response::GameOver {
+                result: GameResult::Resigned(Player::Second),
+                thinking_time: Some([Duration::from_secs(2), Duration::from_secs(7)]),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn finished_game_cant_be_resigned() {
+        let mut state = shared();
+        let (tx, mut rx) = mpsc::channel(4);
+        state.players.insert(1, tx);
+        let now = Instant::now();
+
+        state
+            .take_turn(1, &peer(Player::First), turn(Player::First, 0), now)
+            .unwrap();
+        state.resign(1, &peer(Player::Second), now).unwrap();
+        state.resign(1, &peer(Player::First), now).unwrap();
+
+        // skip the acknowledgement of the Turn and the end of the game
+        rx.recv().await.unwrap();
+        rx.recv().await.unwrap();
+        let refused: Response = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert!(matches!(
+            refused,
+            Response::Error {
+                code: ErrorCode::InvalidMove,
+                ..
+            }
+        ));
+        assert_eq!(state.result, Some(GameResult::Resigned(Player::Second)));
+    }
+
+    #[test]
+    fn resigned_game_is_saved_as_a_resignation() {
+        let mut state = shared();
+        state.started = true;
+        let now = Instant::now();
+        state
+            .take_turn(1, &peer(Player::First), turn(Player::First, 0), now)
+            .unwrap();
+        state.resign(2, &peer(Player::Second), now).unwrap();
+
+        let path = std::env::temp_dir().join(format!("sidestacker-{}.db", state.game_id));
+        let connection = Connection::open(&path).unwrap();
+        create_tables(&connection);
+        state.db_connection = Some(connection);
+        drop(state);
+
+        let result: String = Connection::open(&path)
+            .unwrap()
+            .query_row("SELECT result FROM games", [], |row| row.get(0))
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, r#"{"Resigned":"Second"}"#);
+    }
+
+    #[test]
+    fn seeded_random_turn_order_is_repeatable() {
+        let movers = || {
+            let mut state = Shared::try_new(&Params::from_iter(&[
+                "server",
+                "--no-persist",
+                "--quiet",
+                "--seed",
+                "7",
+                "--turn-order",
+                "random",
+            ]))
+            .unwrap();
+            let mut anyone = peer(Player::First);
+            anyone.self_play = true;
+
+            // alternate sides so no Player can line up a win
+            (0..7)
+                .map(|row| {
+                    let side = if row % 2 == 0 {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    };
+                    let turn = Turn {
+                        source: state.current_player,
+                        mov: Move { side, row },
+                    };
+                    state.take_turn(1, &anyone, turn, Instant::now()).unwrap();
+                    state.current_player
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let first_run = movers();
+        assert_eq!(first_run, movers());
+        // the order really is drawn rather than alternating
+        let alternating = (0..7)
+            .map(|turn| {
+                if turn % 2 == 0 {
+                    Player::Second
+                } else {
+                    Player::First
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_ne!(first_run, alternating);
+    }
+
+    #[test]
+    fn deadlocked_board_is_declared_a_tie_early() {
+        let mut state = Shared::try_new(&Params::from_iter(&[
+            "server",
+            "--no-persist",
+            "--quiet",
+            "-h",
+            "2",
+            "-w",
+            "3",
+            "--win-length",
+            "3",
+        ]))
+        .unwrap();
+        let (first, second) = (peer(Player::First), peer(Player::Second));
+        let now = Instant::now();
+
+        // only whole Rows can win, so once each Row holds both Players'
+        // pieces nobody can
+        state
+            .take_turn(1, &first, turn(Player::First, 0), now)
+            .unwrap();
+        state
+            .take_turn(2, &second, turn(Player::Second, 0), now)
+            .unwrap();
+        state
+            .take_turn(1, &first, turn(Player::First, 1), now)
+            .unwrap();
+        assert_eq!(state.result, None);
+        assert!(state.board.can_anyone_win());
+
+        state
+            .take_turn(2, &second, turn(Player::Second, 1), now)
+            .unwrap();
+        assert_eq!(state.result, Some(GameResult::Tie));
+        assert!(!state.board.is_full());
+    }
+
+    #[test]
+    fn fill_ratio_counts_the_occupied_slots() {
+        let mut state = shared();
+        assert_eq!(state.board.fill_ratio(), 0.0);
+
+        let now = Instant::now();
+        state
+            .take_turn(1, &peer(Player::First), turn(Player::First, 0), now)
+            .unwrap();
+        state
+            .take_turn(2, &peer(Player::Second), turn(Player::Second, 1), now)
+            .unwrap();
+
+        assert_eq!(state.board.fill_ratio(), 2.0 / 49.0);
+    }
+
+    #[test]
+    fn saved_final_board_is_the_replayed_board() {
+        let mut state = shared();
+        let connection = Connection::open_in_memory().unwrap();
+        create_tables(&connection);
+        state.db_connection = Some(connection);
+
+        let now = Instant::now();
+        for row in 0..3 {
+            state
+                .take_turn(1, &peer(Player::First), turn(Player::First, row), now)
+                .unwrap();
+            state
+                .take_turn(2, &peer(Player::Second), turn(Player::Second, row), now)
+                .unwrap();
+        }
+        state.persist().unwrap();
+
+        let encoded: String = state
+            .db_connection
+            .as_ref()
+            .unwrap()
+            .query_row("SELECT final_board FROM games", [], |row| row.get(0))
+            .unwrap();
+        let saved = Board::decode(&state.ruleset, &encoded).unwrap();
+        let replayed = Board::new(&state.ruleset)
+            .apply_turns(&state.turns)
+            .unwrap();
+
+        assert_eq!(saved.encode(), replayed.encode());
+        assert_eq!(saved.encode(), state.board.encode());
+        // dropping the game would save it again
+        state.db_connection = None;
+    }
+
+    #[tokio::test]
+    async fn restricted_row_is_only_played_from_its_side() {
+        let mut state = Shared::try_new(&Params::from_iter(&[
+            "server",
+            "--no-persist",
+            "--quiet",
+            "--restrict-side",
+            "1=right",
+        ]))
+        .unwrap();
+        let (tx, mut rx) = mpsc::channel(4);
+        state.players.insert(1, tx);
+        let first = peer(Player::First);
+        let now = Instant::now();
+
+        state
+            .take_turn(1, &first, turn(Player::First, 1), now)
+            .unwrap();
+        let refused: Response = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert!(matches!(
+            refused,
+            Response::Error {
+                code: ErrorCode::InvalidMove,
+                ..
+            }
+        ));
+        assert!(state.turns.is_empty());
+
+        let allowed = Turn {
+            source: Player::First,
+            mov: Move {
+                side: Side::Right,
+                row: 1,
+            },
+        };
+        state.take_turn(1, &first, allowed, now).unwrap();
+        assert_eq!(state.turns, vec![allowed]);
+        assert!(matches!(
+            state.board.apply_turn(&turn(Player::Second, 1)),
+            Err(ServerError::BlockedSide)
+        ));
+    }
+
+    #[test]
+    fn players_round_trip_through_their_index() {
+        for player in [Player::First, Player::Second] {
+            assert_eq!(Player::from_index(player.index()), Some(player));
+        }
+        assert_eq!(Player::First.index(), 0);
+        assert_eq!(Player::from_index(2), None);
+    }
+
+    #[test]
+    fn swapped_symbols_are_drawn_and_credited_to_the_right_player() {
+        let mut state = shared();
+        state.ruleset.swap_symbols = true;
+        state.board = Board::new(&state.ruleset);
+        let (first, second) = (peer(Player::First), peer(Player::Second));
+        let now = Instant::now();
+
+        state
+            .take_turn(1, &first, turn(Player::First, 0), now)
+            .unwrap();
+        // the first Player plays O when the symbols are swapped
+        assert!(state.board.encode().starts_with("______O/"));
+        let shown = state.board.to_string();
+        assert!(shown.contains('O') && !shown.contains('X'));
+
+        let right = |row| Turn {
+            source: Player::Second,
+            mov: Move {
+                side: Side::Right,
+                row,
+            },
+        };
+        state.take_turn(2, &second, right(0), now).unwrap();
+        assert!(state.board.encode().starts_with("X_____O/"));
+        for row in 1..3 {
+            state
+                .take_turn(1, &first, turn(Player::First, row), now)
+                .unwrap();
+            state.take_turn(2, &second, right(row), now).unwrap();
+        }
+        state
+            .take_turn(1, &first, turn(Player::First, 3), now)
+            .unwrap();
+
+        assert_eq!(state.result, Some(GameResult::Win(Player::First)));
+    }
+
+    #[test]
+    fn blitz_preset_is_a_quick_game() {
+        let ruleset = Params::from_iter(&["server", "--preset", "blitz"]).ruleset();
+
+        assert_eq!((ruleset.height, ruleset.width), (5, 5));
+        assert_eq!(ruleset.win_length, 4);
+        assert_eq!(ruleset.turn_timeout_secs, Some(10));
+
+        // the flags for individual rules win over the preset
+        let ruleset = Params::from_iter(&["server", "--preset", "blitz", "-h", "6"]).ruleset();
+        assert_eq!((ruleset.height, ruleset.width), (6, 5));
+    }
+
+    #[test]
+    fn near_win_start_position_is_won_by_the_player_to_move() {
+        let mut state = Shared::try_new(&Params::from_iter(&[
+            "server",
+            "--no-persist",
+            "--quiet",
+            "--start-position",
+            "O__X/O__X/O__X/____",
+        ]))
+        .unwrap();
+        let now = Instant::now();
+
+        // each Player has three in a column, and it's the first Player's
+        // turn since they've placed no more pieces than the second
+        assert_eq!(state.current_player, Player::First);
+        state
+            .take_turn(2, &peer(Player::Second), turn(Player::Second, 3), now)
+            .unwrap();
+        assert!(state.turns.is_empty());
+
+        state
+            .take_turn(1, &peer(Player::First), turn(Player::First, 3), now)
+            .unwrap();
+        assert_eq!(state.result, Some(GameResult::Win(Player::First)));
+    }
 }
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use crate::ClientId;
+
+/// The formats the server can write its log in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// Free-form, human-readable lines.
+    Text,
+    /// One JSON object per line, for log pipelines.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Unknown log format '{}', expected 'text' or 'json'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Something that happened on the server.
+#[derive(Debug)]
+pub struct Event {
+    /// What kind of thing happened, e.g. `join` or `disconnect`.
+    pub kind: &'static str,
+    /// A human-readable description of what happened.
+    pub message: String,
+    /// The client involved, if any.
+    pub client: Option<ClientId>,
+    /// The address the client connected from, if known.
+    pub addr: Option<String>,
+    /// The number of the Player involved, if any.
+    pub player: Option<u32>,
+    /// The id the client's connection was given when it was accepted, if
+    /// known.
+    pub connection: Option<String>,
+    /// The id of the game the Event happened in, if any.
+    pub game: Option<String>,
+}
+
+impl Event {
+    /// Create a new Event of the given kind.
+    pub fn new(kind: &'static str, message: impl Into<String>) -> Self {
+        Event {
+            kind,
+            message: message.into(),
+            client: None,
+            addr: None,
+            player: None,
+            connection: None,
+            game: None,
+        }
+    }
+
+    /// Attach the client involved in the Event.
+    pub fn client(mut self, client: ClientId) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Attach the address the client involved connected from.
+    pub fn addr(mut self, addr: impl Into<String>) -> Self {
+        self.addr = Some(addr.into());
+        self
+    }
+
+    /// Attach the number of the Player involved in the Event.
+    pub fn player(mut self, player: u32) -> Self {
+        self.player = Some(player);
+        self
+    }
+
+    /// Attach the id of the involved client's connection.
+    pub fn connection(mut self, connection: impl Into<String>) -> Self {
+        self.connection = Some(connection.into());
+        self
+    }
+
+    /// Attach the id of the game the Event happened in.
+    pub fn game(mut self, game: impl Into<String>) -> Self {
+        self.game = Some(game.into());
+        self
+    }
+
+    /// Render the Event as a line of JSON at the given level.
+    pub fn to_json(&self, level: &str) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+
+        json!({
+            "timestamp": timestamp as u64,
+            "level": level,
+            "event": self.kind,
+            "message": self.message,
+            "client": self.client,
+            "addr": self.addr,
+            "player": self.player,
+            "connection": self.connection,
+            "game": self.game,
+        })
+        .to_string()
+    }
+}
+
+/// Writes Events in the configured format.
+#[derive(Debug, Clone, Copy)]
+pub struct Logger {
+    /// The format Events are written in.
+    pub format: LogFormat,
+    /// Whether informational Events are suppressed.
+    pub quiet: bool,
+}
+
+impl Logger {
+    /// Write an informational Event to stdout, unless quiet.
+    pub fn info(&self, event: Event) {
+        if let Some(line) = self.line(&event, "info") {
+            println!("{}", line);
+        }
+    }
+
+    /// Write an error Event to stderr.
+    pub fn error(&self, event: Event) {
+        if let Some(line) = self.line(&event, "error") {
+            eprintln!("{}", line);
+        }
+    }
+
+    /// The line an Event is written as at the given level, or `None` if it
+    /// isn't written at all. Only informational Events are ever silenced.
+    fn line(&self, event: &Event, level: &str) -> Option<String> {
+        if self.quiet && level == "info" {
+            return None;
+        }
+
+        Some(match self.format {
+            LogFormat::Text => event.message.clone(),
+            LogFormat::Json => event.to_json(level),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger(quiet: bool) -> Logger {
+        Logger {
+            format: LogFormat::Text,
+            quiet,
+        }
+    }
+
+    #[test]
+    fn quiet_silences_informational_events() {
+        let event = Event::new("join", "Player 1 joined the game.");
+
+        assert_eq!(logger(true).line(&event, "info"), None);
+        assert_eq!(
+            logger(false).line(&event, "info").as_deref(),
+            Some("Player 1 joined the game.")
+        );
+    }
+
+    #[test]
+    fn quiet_still_reports_errors() {
+        let event = Event::new("error", "Couldn't save the game.");
+
+        assert_eq!(
+            logger(true).line(&event, "error").as_deref(),
+            Some("Couldn't save the game.")
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{GameResult, Player};
+
+/// A series of games between the same two Players, won by whoever first
+/// wins the majority of them. Ties count for no one, so a Match that runs
+/// out of games with the scores level is drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct Match {
+    /// The most games the Match lasts.
+    pub games: u32,
+    /// How many games a Player must win to win the Match.
+    pub games_to_win: u32,
+    /// How many games have been played so far.
+    pub played: u32,
+    /// The games each Player has won so far, by `Player::index`.
+    pub scores: [u32; 2],
+    /// The Player who moves first in the game being played.
+    pub first_player: Player,
+}
+
+impl Match {
+    /// Start a best-of-`games` Match whose first game is opened by the
+    /// given Player.
+    pub fn best_of(games: u32, first_player: Player) -> Self {
+        Match {
+            games,
+            games_to_win: games / 2 + 1,
+            played: 0,
+            scores: [0; 2],
+            first_player,
+        }
+    }
+
+    /// Count a finished game towards the Match, and hand the first move of
+    /// the next game to the other Player. Returns `None` while the Match
+    /// goes on, `Some(None)` once it's over and drawn, and
+    /// `Some(Some(player))` once it's been won.
+    pub fn record(&mut self, result: GameResult) -> Option<Option<Player>> {
+        let winner = match result {
+            GameResult::Win(player) => Some(player),
+            GameResult::Resigned(player) => Some(!player),
+            _ => None,
+        };
+
+        if let Some(winner) = winner {
+            self.scores[winner.index()] += 1;
+        }
+        self.played += 1;
+        self.first_player = !self.first_player;
+
+        match self.winner() {
+            Some(winner) => Some(Some(winner)),
+            None if self.played >= self.games => Some(self.leader()),
+            None => None,
+        }
+    }
+
+    /// The Player who has won the Match, if either has yet.
+    pub fn winner(&self) -> Option<Player> {
+        [Player::First, Player::Second]
+            .iter()
+            .copied()
+            .find(|player| self.scores[player.index()] >= self.games_to_win)
+    }
+
+    /// The Player who has won more games, unless the scores are level.
+    fn leader(&self) -> Option<Player> {
+        let [first, second] = self.scores;
+
+        match first.cmp(&second) {
+            std::cmp::Ordering::Greater => Some(Player::First),
+            std::cmp::Ordering::Less => Some(Player::Second),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_of_three_ends_once_a_player_wins_two() {
+        let mut series = Match::best_of(3, Player::First);
+
+        assert_eq!(series.record(GameResult::Win(Player::Second)), None);
+        assert_eq!(series.first_player, Player::Second);
+        assert_eq!(
+            series.record(GameResult::Resigned(Player::First)),
+            Some(Some(Player::Second))
+        );
+        assert_eq!(series.scores, [0, 2]);
+        assert_eq!(series.played, 2);
+    }
+
+    #[test]
+    fn match_of_ties_ends_drawn() {
+        let mut series = Match::best_of(3, Player::First);
+
+        assert_eq!(series.record(GameResult::Tie), None);
+        assert_eq!(series.record(GameResult::Tie), None);
+        assert_eq!(series.record(GameResult::Tie), Some(None));
+        assert_eq!(series.scores, [0, 0]);
+    }
+
+    #[test]
+    fn leader_wins_a_match_that_runs_out_of_games() {
+        let mut series = Match::best_of(3, Player::First);
+
+        assert_eq!(series.record(GameResult::Tie), None);
+        assert_eq!(series.record(GameResult::Win(Player::First)), None);
+        assert_eq!(series.record(GameResult::Tie), Some(Some(Player::First)));
+    }
+}
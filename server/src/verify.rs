@@ -0,0 +1,209 @@
+use rusqlite::Connection;
+
+use crate::error::ServerError;
+use crate::game::Board;
+use crate::{GameResult, Ruleset, Turn};
+
+/// A stored game that doesn't hold up when it is replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The id the game was saved with.
+    pub id: i64,
+    /// What is wrong with it.
+    pub reason: String,
+}
+
+/// Replay every game stored in the database under the Ruleset it was saved
+/// with, or the given one for games saved without theirs, and report each
+/// one whose Turns couldn't have been played, or whose recorded result isn't
+/// the one its Turns lead to. Also returns how many games were checked.
+pub fn verify_games(
+    connection: &Connection,
+    ruleset: &Ruleset,
+) -> Result<(usize, Vec<Problem>), ServerError> {
+    let mut statement = connection
+        .prepare("SELECT id, turns, start_position, result, ruleset FROM games ORDER BY id")?;
+    let games = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let problems = games
+        .iter()
+        .filter_map(|(id, turns, start_position, result, saved_ruleset)| {
+            let reason = saved_ruleset_or(ruleset, saved_ruleset.as_deref())
+                .and_then(|ruleset| {
+                    check_game(
+                        &ruleset,
+                        turns,
+                        start_position.as_deref(),
+                        result.as_deref(),
+                    )
+                })
+                .err()?;
+
+            Some(Problem { id: *id, reason })
+        })
+        .collect();
+
+    Ok((games.len(), problems))
+}
+
+/// The Ruleset a game was saved with, or the given one if it was saved
+/// without it.
+fn saved_ruleset_or(ruleset: &Ruleset, saved: Option<&str>) -> Result<Ruleset, String> {
+    match saved {
+        Some(saved) => {
+            serde_json::from_str(saved).map_err(|e| format!("its Ruleset can't be read: {}", e))
+        }
+        None => Ok(ruleset.clone()),
+    }
+}
+
+/// Check that the Turns read from a game file could have been played, one
+/// after the other, on an empty Board under the given Ruleset, explaining
+/// what's wrong with them if not.
+pub fn validate_turns(ruleset: &Ruleset, turns: &str) -> Result<(), String> {
+    check_game(ruleset, turns, None, None)
+}
+
+/// Replay a single stored game, explaining what's wrong with it if anything.
+fn check_game(
+    ruleset: &Ruleset,
+    turns: &str,
+    start_position: Option<&str>,
+    result: Option<&str>,
+) -> Result<(), String> {
+    let turns: Vec<Turn> =
+        serde_json::from_str(turns).map_err(|e| format!("its Turns can't be read: {}", e))?;
+    let recorded: Option<GameResult> = match result {
+        Some(result) => {
+            serde_json::from_str(result).map_err(|e| format!("its result can't be read: {}", e))?
+        }
+        None => None,
+    };
+
+    let mut board = match start_position {
+        Some(encoded) => Board::decode(ruleset, encoded)
+            .map_err(|e| format!("its starting position can't be read: {}", e))?,
+        None => Board::new(ruleset),
+    };
+
+    let mut winner = None;
+    for (ply, turn) in turns.iter().enumerate() {
+        if winner.is_some() {
+            return Err(format!(
+                "play went on after the game was won at Turn {}",
+                ply
+            ));
+        }
+
+        let (row, col) = board
+            .apply_turn(turn)
+            .map_err(|e| format!("Turn {} couldn't have been played: {}", ply + 1, e))?;
+        winner = board.winner_at(row, col);
+    }
+
+    // games saved before ties were called early may have played on after
+    // no one could win, so ties are only judged by the final position
+    let replayed = match winner {
+        Some(winner) => Some(GameResult::Win(winner)),
+        None if !board.can_anyone_win() => Some(GameResult::Tie),
+        None => None,
+    };
+
+    // a game that ended some other way, e.g. by resigning, can't be checked
+    // against its Turns, but it can't have been won on the Board either
+    let decided_on_board = matches!(recorded, Some(GameResult::Win(_)) | Some(GameResult::Tie))
+        || matches!(replayed, Some(GameResult::Win(_)));
+    if decided_on_board && recorded != replayed {
+        return Err(format!(
+            "it was recorded as {}, but replaying it gives {}",
+            describe(&recorded),
+            describe(&replayed)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Describe how a game ended, or that it didn't.
+fn describe(result: &Option<GameResult>) -> String {
+    match result {
+        Some(result) => format!("{:?}", result),
+        None => String::from("unfinished"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_tables;
+
+    /// A Turn of inserting into the given Row from the given Side, as it's
+    /// stored.
+    fn turn(source: &str, row: usize, side: &str) -> String {
+        format!(
+            r#"{{"source":"{}","mov":{{"side":"{}","row":{}}}}}"#,
+            source, side, row
+        )
+    }
+
+    /// The Turns of a game the first Player wins with their fourth piece,
+    /// up to the given number of them.
+    fn won_game(plies: usize) -> String {
+        let turns = (0..4)
+            .flat_map(|row| vec![turn("First", row, "Left"), turn("Second", row, "Right")])
+            .take(plies)
+            .collect::<Vec<_>>();
+
+        format!("[{}]", turns.join(","))
+    }
+
+    #[test]
+    fn verify_flags_only_the_bad_games() {
+        let connection = Connection::open_in_memory().unwrap();
+        create_tables(&connection);
+        let games = [
+            // won, and recorded as such
+            (won_game(7), Some(r#"{"Win":"First"}"#)),
+            // not finished yet
+            (won_game(3), None),
+            // played into a Row that isn't on the Board
+            (format!("[{}]", turn("First", 9, "Left")), None),
+            // recorded with the wrong winner
+            (won_game(7), Some(r#"{"Win":"Second"}"#)),
+            // play went on after the game was won
+            (won_game(8), Some(r#"{"Win":"First"}"#)),
+            // not Turns at all
+            (String::from("not json"), None),
+        ];
+        for (turns, result) in &games {
+            connection
+                .execute(
+                    "INSERT INTO games (turns, result) values (?1, ?2)",
+                    rusqlite::params![turns, result],
+                )
+                .unwrap();
+        }
+
+        let (checked, problems) = verify_games(&connection, &Ruleset::standard()).unwrap();
+
+        assert_eq!(checked, games.len());
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| problem.id)
+                .collect::<Vec<_>>(),
+            vec![3, 4, 5, 6]
+        );
+        assert!(problems[1].reason.contains("recorded as Win(Second)"));
+    }
+}
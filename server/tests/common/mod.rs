@@ -0,0 +1,252 @@
+// each test file uses its own share of these helpers
+#![allow(dead_code)]
+
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{sink::SinkExt, StreamExt};
+use serde_json::json;
+use structopt::StructOpt;
+use tokio::io::{duplex, AsyncBufReadExt, BufReader, DuplexStream, Lines};
+use tokio::net::TcpStream;
+use tokio::process::{Child, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+use tokio_util::codec::{Framed, LinesCodec};
+
+use server::error::ServerError;
+use server::{observe, process, ClientId, GameResult, Params, Player, Request, Response, Shared};
+
+/// How long to wait for a message that should arrive.
+const PATIENCE: Duration = Duration::from_secs(5);
+
+/// How long to wait before deciding that a message isn't coming.
+const QUIET_PERIOD: Duration = Duration::from_millis(200);
+
+/// Start a game with the given server flags. Nothing is ever saved to the
+/// database or printed.
+pub fn game(flags: &[&str]) -> Arc<Mutex<Shared>> {
+    let args = ["server", "--no-persist", "--quiet"]
+        .iter()
+        .chain(flags)
+        .copied()
+        .collect::<Vec<_>>();
+
+    Arc::new(Mutex::new(
+        Shared::try_new(&Params::from_iter(args)).expect("couldn't start the game"),
+    ))
+}
+
+/// Find a port on localhost that nothing is listening on.
+pub fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("no free port on localhost")
+}
+
+/// Run the server binary from the given directory with the given
+/// arguments, returning it along with the lines it logs. The server is
+/// killed if it's dropped while still running.
+pub fn spawn_server(dir: &Path, args: &[&str]) -> (Child, Lines<BufReader<ChildStdout>>) {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_server"))
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("couldn't start the server");
+    let log = BufReader::new(server.stdout.take().unwrap()).lines();
+
+    (server, log)
+}
+
+/// Interrupt the server binary, as Ctrl-C would, and wait for it to shut
+/// down.
+pub async fn interrupt(server: &mut Child) {
+    let interrupted = std::process::Command::new("kill")
+        .args(["-INT", &server.id().unwrap().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+
+    timeout(PATIENCE, server.wait())
+        .await
+        .expect("the server didn't shut down")
+        .unwrap();
+}
+
+/// Connect to a server over TCP, waiting for it to start listening.
+pub async fn connect_tcp(addr: SocketAddr) -> Framed<TcpStream, LinesCodec> {
+    loop {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => return Framed::new(stream, LinesCodec::new()),
+            Err(_) => sleep(Duration::from_millis(20)).await,
+        }
+    }
+}
+
+/// Send a Request to a server over a TCP connection.
+pub async fn send_tcp(lines: &mut Framed<TcpStream, LinesCodec>, request: &Request) {
+    lines
+        .send(serde_json::to_string(request).unwrap())
+        .await
+        .unwrap();
+}
+
+/// A client connected straight to the server's `process`, without a socket
+/// in between.
+pub struct TestClient {
+    lines: Framed<DuplexStream, LinesCodec>,
+    /// The server's side of the connection, which finishes with how the game
+    /// ended for this client.
+    pub task: JoinHandle<Result<Option<GameResult>, ServerError>>,
+}
+
+impl TestClient {
+    /// Connect a new client with the given id to the game.
+    pub fn connect(state: &Arc<Mutex<Shared>>, id: ClientId) -> Self {
+        let (client, server) = duplex(4096);
+
+        TestClient {
+            lines: Framed::new(client, LinesCodec::new()),
+            task: tokio::spawn(process(Arc::clone(state), server, id)),
+        }
+    }
+
+    /// Connect a new client with the given id to the observer port.
+    pub fn observe(state: &Arc<Mutex<Shared>>, id: ClientId) -> Self {
+        let (client, server) = duplex(4096);
+        let state = Arc::clone(state);
+
+        TestClient {
+            lines: Framed::new(client, LinesCodec::new()),
+            task: tokio::spawn(async move { observe(state, server, id).await.map(|_| None) }),
+        }
+    }
+
+    /// Connect a new client that opens with the given Request, returning
+    /// the client and the server's first Response.
+    pub async fn open(
+        state: &Arc<Mutex<Shared>>,
+        id: ClientId,
+        request: &Request,
+    ) -> (Self, Response) {
+        let mut client = Self::connect(state, id);
+        client.send(request).await;
+        let response = client.recv().await;
+
+        (client, response)
+    }
+
+    /// Connect a new client and join the game with it, returning the client
+    /// and the Player it was seated as.
+    pub async fn join(state: &Arc<Mutex<Shared>>, id: ClientId) -> (Self, Player) {
+        match Self::open(state, id, &Request::Join).await {
+            (client, Response::Welcome { player, .. }) => (client, player),
+            (_, response) => panic!("expected a Welcome, got {:?}", response),
+        }
+    }
+
+    /// Send a Request to the server.
+    pub async fn send(&mut self, request: &Request) {
+        self.send_line(&serde_json::to_string(request).unwrap())
+            .await;
+    }
+
+    /// Send a raw line to the server, whether or not it's a valid Request.
+    pub async fn send_line(&mut self, line: &str) {
+        self.lines.send(line).await.expect("the server hung up");
+    }
+
+    /// Wait for the server's next Response.
+    pub async fn recv(&mut self) -> Response {
+        let line = timeout(PATIENCE, self.lines.next())
+            .await
+            .expect("timed out waiting for the server")
+            .expect("the server hung up")
+            .expect("couldn't read the server's line");
+
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// Skip Responses until one matches the given predicate, and return it.
+    pub async fn recv_until(&mut self, wanted: impl Fn(&Response) -> bool) -> Response {
+        loop {
+            let response = self.recv().await;
+            if wanted(&response) {
+                return response;
+            }
+        }
+    }
+
+    /// The server's next Response, unless it sends nothing for a while.
+    pub async fn try_recv(&mut self) -> Option<Response> {
+        match timeout(QUIET_PERIOD, self.lines.next()).await {
+            Ok(Some(Ok(line))) => Some(serde_json::from_str(&line).unwrap()),
+            _ => None,
+        }
+    }
+
+    /// Whether the server has closed the connection, waiting for it to if
+    /// it hasn't yet.
+    pub async fn is_closed(&mut self) -> bool {
+        loop {
+            match timeout(PATIENCE, self.lines.next()).await {
+                Ok(Some(Ok(_))) => continue,
+                Ok(_) => return true,
+                Err(_) => return false,
+            }
+        }
+    }
+
+    /// Hang up, as if the client's connection dropped.
+    pub async fn disconnect(self) -> Result<Option<GameResult>, ServerError> {
+        drop(self.lines);
+        self.task.await.unwrap()
+    }
+}
+
+/// Two clients seated in a game that has started, as the first and second
+/// Player.
+pub async fn start(state: &Arc<Mutex<Shared>>) -> (TestClient, TestClient) {
+    let ((first, _), (second, _)) = start_with_welcomes(state).await;
+
+    (first, second)
+}
+
+/// Two clients seated in a game that has started, as the first and second
+/// Player, along with the Welcome each was sent.
+pub async fn start_with_welcomes(
+    state: &Arc<Mutex<Shared>>,
+) -> ((TestClient, Response), (TestClient, Response)) {
+    let (mut first, first_welcome) = TestClient::open(state, 1, &Request::Join).await;
+    let (mut second, second_welcome) = TestClient::open(state, 2, &Request::Join).await;
+
+    first.recv_until(|r| *r == Response::GameStart).await;
+    second.recv_until(|r| *r == Response::GameStart).await;
+
+    ((first, first_welcome), (second, second_welcome))
+}
+
+/// The reconnect token the server gave in a Welcome.
+pub fn reconnect_token(welcome: &Response) -> String {
+    match welcome {
+        Response::Welcome {
+            reconnect_token: Some(token),
+            ..
+        } => token.clone(),
+        response => panic!("expected a Welcome with a token, got {:?}", response),
+    }
+}
+
+/// A Request to take a Turn, on behalf of the given Player, of inserting
+/// into the given Row from the given Side, e.g. `"Left"`.
+pub fn turn(source: Player, row: usize, side: &str) -> Request {
+    let turn = json!({ "source": source, "mov": { "side": side, "row": row } });
+
+    Request::Turn(serde_json::from_value(turn).unwrap())
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{game, start, TestClient};
+use server::Response;
+
+#[tokio::test]
+async fn malformed_line_frees_the_seat() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+
+    first.send_line("this isn't a Request").await;
+    assert!(first.is_closed().await);
+    assert!(first.disconnect().await.is_err());
+
+    second
+        .recv_until(|r| *r == Response::PlayerDisconnected)
+        .await;
+    let state_guard = state.lock().await;
+    assert_eq!(state_guard.seats.len(), 1);
+    assert!(!state_guard.players.contains_key(&1));
+}
+
+#[tokio::test]
+async fn freed_seat_can_be_taken_before_the_game_starts() {
+    let state = game(&[]);
+    let (mut first, _) = TestClient::join(&state, 1).await;
+
+    first.send_line("{").await;
+    assert!(first.disconnect().await.is_err());
+
+    let (_, player) = TestClient::join(&state, 2).await;
+    assert_eq!(player, server::Player::First);
+}
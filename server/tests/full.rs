@@ -0,0 +1,76 @@
+mod common;
+
+use futures::{SinkExt, StreamExt};
+use structopt::StructOpt;
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use common::{connect_tcp, free_addr, game, start, TestClient};
+use server::{run_server, ErrorCode, Params, Request, Response};
+
+/// Send a Request over a TCP connection and wait for the server's answer.
+async fn ask(lines: &mut Framed<TcpStream, LinesCodec>, request: &Request) -> Response {
+    lines
+        .send(serde_json::to_string(request).unwrap())
+        .await
+        .unwrap();
+    let line = lines.next().await.unwrap().unwrap();
+
+    serde_json::from_str(&line).unwrap()
+}
+
+#[tokio::test]
+async fn third_player_is_turned_away_without_an_error() {
+    let state = game(&[]);
+    let (_first, _second) = start(&state).await;
+
+    let (mut third, response) = TestClient::open(&state, 3, &Request::Join).await;
+    assert_eq!(response, Response::GameInProgress);
+    assert!(third.is_closed().await);
+    assert_eq!(third.disconnect().await.unwrap(), None);
+
+    assert_eq!(state.lock().await.seats.len(), 2);
+}
+
+#[tokio::test]
+async fn connection_over_the_limit_is_refused() {
+    let addr = free_addr();
+    let params = Params::from_iter(&[
+        "server",
+        "-a",
+        &addr.to_string(),
+        "--max-connections",
+        "2",
+        "--no-persist",
+        "--quiet",
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(run_server(params, on_shutdown));
+
+    let mut first = connect_tcp(addr).await;
+    let mut second = connect_tcp(addr).await;
+    // spectators stay connected, so both count against the limit
+    assert!(matches!(
+        ask(&mut first, &Request::Spectate).await,
+        Response::BoardState { .. }
+    ));
+    assert!(matches!(
+        ask(&mut second, &Request::Spectate).await,
+        Response::BoardState { .. }
+    ));
+
+    let mut third = connect_tcp(addr).await;
+    let refused = third.next().await.unwrap().unwrap();
+    assert!(matches!(
+        serde_json::from_str(&refused).unwrap(),
+        Response::Error {
+            code: ErrorCode::ServerFull,
+            ..
+        }
+    ));
+    assert!(third.next().await.is_none());
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use futures::StreamExt;
+use rusqlite::Connection;
+use serde_json::Value;
+use tokio::time::timeout;
+
+use common::{connect_tcp, free_addr, interrupt, send_tcp, spawn_server, turn};
+use server::{Player, Request, Response};
+
+#[tokio::test]
+async fn saved_game_has_the_id_it_was_logged_with() {
+    // the server keeps its database in `../db`, so it's run from a
+    // directory of its own to keep this game apart from any others
+    let root = std::env::temp_dir().join(format!("sidestacker-game-id-{}", std::process::id()));
+    let workdir = root.join("server");
+    fs::create_dir_all(&workdir).unwrap();
+    fs::create_dir_all(root.join("db")).unwrap();
+
+    let addr = free_addr();
+    let (mut server, mut log) = spawn_server(
+        &workdir,
+        &["start", "-a", &addr.to_string(), "--log-format", "json"],
+    );
+
+    // join in a known order, then take a Turn so there's a game to save
+    let mut first = connect_tcp(addr).await;
+    send_tcp(&mut first, &Request::Join).await;
+    first.next().await.unwrap().unwrap();
+    let mut second = connect_tcp(addr).await;
+    send_tcp(&mut second, &Request::Join).await;
+    send_tcp(&mut first, &turn(Player::First, 0, "Left")).await;
+    loop {
+        let line = first.next().await.unwrap().unwrap();
+        if let Response::Acknowledged { .. } = serde_json::from_str(&line).unwrap() {
+            break;
+        }
+    }
+
+    let logged = timeout(Duration::from_secs(5), async {
+        while let Some(line) = log.next_line().await.unwrap() {
+            let event: Value = serde_json::from_str(&line).unwrap();
+            if event["event"] == "join" {
+                return event["game"].as_str().unwrap().to_string();
+            }
+        }
+        panic!("the server stopped logging");
+    })
+    .await
+    .expect("timed out waiting for a Player to join");
+
+    // the game is saved as the server shuts down
+    interrupt(&mut server).await;
+
+    let saved: String = Connection::open(root.join("db").join("games.db"))
+        .unwrap()
+        .query_row("SELECT uuid FROM games", [], |row| row.get(0))
+        .unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(saved, logged);
+}
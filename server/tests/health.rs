@@ -0,0 +1,43 @@
+mod common;
+
+use std::time::Duration;
+
+use structopt::StructOpt;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio::time::sleep;
+
+use common::free_addr;
+use server::{run_server, Params};
+
+#[tokio::test]
+async fn health_check_succeeds_before_any_game() {
+    let (addr, health_addr) = (free_addr().to_string(), free_addr().to_string());
+    let params = Params::from_iter(&[
+        "server",
+        "-a",
+        &addr,
+        "--health-addr",
+        &health_addr,
+        "--no-persist",
+        "--quiet",
+    ]);
+    let (shutdown, on_shutdown) = oneshot::channel();
+    let server = tokio::spawn(run_server(params, on_shutdown));
+
+    let mut probe = loop {
+        match TcpStream::connect(&health_addr).await {
+            Ok(probe) => break probe,
+            // the server may not be listening yet
+            Err(_) => sleep(Duration::from_millis(20)).await,
+        }
+    };
+    let mut answer = String::new();
+    probe.read_to_string(&mut answer).await.unwrap();
+
+    assert_eq!(answer, "OK\n");
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
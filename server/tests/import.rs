@@ -0,0 +1,49 @@
+mod common;
+
+use std::fs;
+
+use common::spawn_server;
+
+#[tokio::test]
+async fn dry_run_reports_on_each_game_and_saves_nothing() {
+    // the server would keep its database in `../db`
+    let root = std::env::temp_dir().join(format!("sidestacker-import-{}", std::process::id()));
+    let workdir = root.join("server");
+    fs::create_dir_all(&workdir).unwrap();
+    fs::create_dir_all(root.join("db")).unwrap();
+
+    let games = [
+        (
+            "valid.json",
+            r#"[{"source":"First","mov":{"side":"Left","row":0}},
+                {"source":"Second","mov":{"side":"Right","row":0}}]"#,
+        ),
+        (
+            "off_the_board.json",
+            r#"[{"source":"First","mov":{"side":"Left","row":0}},
+                {"source":"Second","mov":{"side":"Right","row":99}}]"#,
+        ),
+        ("garbled.json", "not turns at all"),
+    ];
+    for (name, turns) in &games {
+        fs::write(workdir.join(name), turns).unwrap();
+    }
+
+    let mut args = vec!["import", "--dry-run"];
+    args.extend(games.iter().map(|(name, _)| *name));
+    let (mut server, mut log) = spawn_server(&workdir, &args);
+    let mut lines = Vec::new();
+    while let Some(line) = log.next_line().await.unwrap() {
+        lines.push(line);
+    }
+    let status = server.wait().await.unwrap();
+    let saved = fs::read_dir(root.join("db")).unwrap().count();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(!status.success());
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "valid.json: OK");
+    assert!(lines[1].starts_with("off_the_board.json: Turn 2 couldn't have been played"));
+    assert!(lines[2].starts_with("garbled.json: its Turns can't be read"));
+    assert_eq!(saved, 0);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::Value;
+use tokio::time::timeout;
+
+use common::{connect_tcp, free_addr, interrupt, send_tcp, spawn_server};
+use server::Request;
+
+#[tokio::test]
+async fn join_is_logged_as_json() {
+    let root = std::env::temp_dir().join(format!("sidestacker-log-format-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+
+    let addr = free_addr();
+    let (mut server, mut log) = spawn_server(
+        &root,
+        &[
+            "start",
+            "-a",
+            &addr.to_string(),
+            "--no-persist",
+            "--log-format",
+            "json",
+        ],
+    );
+
+    let mut first = connect_tcp(addr).await;
+    send_tcp(&mut first, &Request::Join).await;
+    first.next().await.unwrap().unwrap();
+
+    let join = timeout(Duration::from_secs(5), async {
+        while let Some(line) = log.next_line().await.unwrap() {
+            // every line is JSON, not just the join
+            let event: Value = serde_json::from_str(&line).unwrap();
+            if event["event"] == "join" {
+                return event;
+            }
+        }
+        panic!("the server stopped logging");
+    })
+    .await
+    .expect("timed out waiting for a Player to join");
+
+    interrupt(&mut server).await;
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(join["level"], "info");
+    assert_eq!(join["player"], 1);
+    assert!(join["client"].is_u64());
+    assert!(join["timestamp"].is_u64());
+    assert!(join["game"].is_string());
+    assert!(join["message"].as_str().unwrap().contains("Player 1"));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::fs;
+
+use futures::StreamExt;
+use tokio::net::TcpStream;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use common::{connect_tcp, free_addr, interrupt, send_tcp, spawn_server, turn};
+use server::{Player, Request, Response};
+
+/// Take a Turn and wait for the server to accept it, returning whether it
+/// ended the game.
+async fn play(lines: &mut Framed<TcpStream, LinesCodec>, source: Player, row: usize) -> bool {
+    let side = match source {
+        Player::First => "Left",
+        Player::Second => "Right",
+    };
+    send_tcp(lines, &turn(source, row, side)).await;
+
+    loop {
+        let line = lines.next().await.unwrap().unwrap();
+        if let Response::Acknowledged { game_over, .. } = serde_json::from_str(&line).unwrap() {
+            return game_over.is_some();
+        }
+    }
+}
+
+#[tokio::test]
+async fn game_without_persistence_leaves_no_database() {
+    // the server would keep its database in `../db`
+    let root = std::env::temp_dir().join(format!("sidestacker-no-persist-{}", std::process::id()));
+    let workdir = root.join("server");
+    fs::create_dir_all(&workdir).unwrap();
+    fs::create_dir_all(root.join("db")).unwrap();
+
+    let addr = free_addr();
+    let (mut server, _log) = spawn_server(
+        &workdir,
+        &["start", "-a", &addr.to_string(), "--no-persist", "--quiet"],
+    );
+
+    let mut first = connect_tcp(addr).await;
+    send_tcp(&mut first, &Request::Join).await;
+    first.next().await.unwrap().unwrap();
+    let mut second = connect_tcp(addr).await;
+    send_tcp(&mut second, &Request::Join).await;
+
+    // the first Player fills a column from the left, and wins with their
+    // fourth piece
+    for row in 0..3 {
+        assert!(!play(&mut first, Player::First, row).await);
+        assert!(!play(&mut second, Player::Second, row).await);
+    }
+    assert!(play(&mut first, Player::First, 3).await);
+
+    interrupt(&mut server).await;
+
+    assert_eq!(fs::read_dir(root.join("db")).unwrap().count(), 0);
+    fs::remove_dir_all(&root).unwrap();
+}
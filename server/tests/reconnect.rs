@@ -0,0 +1,72 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+use common::{game, reconnect_token, start_with_welcomes, TestClient};
+use server::{Player, Request, Response};
+
+#[tokio::test]
+async fn rejoin_needs_the_seats_token() {
+    let state = game(&["--reconnect-grace-secs", "30"]);
+    let ((first, welcome), (mut second, _)) = start_with_welcomes(&state).await;
+    let token = reconnect_token(&welcome);
+    first.disconnect().await.unwrap();
+
+    let forged = Request::Rejoin {
+        player: Player::First,
+        token: String::from("not-the-token"),
+    };
+    let (mut impostor, response) = TestClient::open(&state, 3, &forged).await;
+    assert_eq!(response, Response::GameInProgress);
+    assert!(impostor.is_closed().await);
+
+    let rejoin = Request::Rejoin {
+        player: Player::First,
+        token,
+    };
+    let (mut first, response) = TestClient::open(&state, 4, &rejoin).await;
+    match response {
+        Response::Welcome { player, .. } => assert_eq!(player, Player::First),
+        response => panic!("expected a Welcome, got {:?}", response),
+    }
+    assert!(matches!(first.recv().await, Response::BoardState { .. }));
+
+    // the other Player never hears that anyone left
+    assert!(!matches!(
+        second.try_recv().await,
+        Some(Response::PlayerDisconnected)
+    ));
+}
+
+#[tokio::test]
+async fn tokens_are_only_issued_when_seats_are_held() {
+    let state = game(&[]);
+    let ((_first, welcome), _) = start_with_welcomes(&state).await;
+
+    assert!(matches!(
+        welcome,
+        Response::Welcome {
+            reconnect_token: None,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn expired_token_is_rejected() {
+    let state = game(&["--reconnect-grace-secs", "30", "--token-ttl-secs", "1"]);
+    let ((first, welcome), _second) = start_with_welcomes(&state).await;
+    let token = reconnect_token(&welcome);
+    first.disconnect().await.unwrap();
+    sleep(Duration::from_millis(1100)).await;
+
+    let rejoin = Request::Rejoin {
+        player: Player::First,
+        token,
+    };
+    let (mut late, response) = TestClient::open(&state, 3, &rejoin).await;
+    assert_eq!(response, Response::GameInProgress);
+    assert!(late.is_closed().await);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{game, turn, TestClient};
+use server::{GameResult, Player, Request, Response};
+
+#[tokio::test]
+async fn one_connection_plays_a_whole_game() {
+    let state = game(&["--allow-self-play"]);
+    let (mut client, welcome) = TestClient::open(&state, 1, &Request::SelfPlay).await;
+    assert!(matches!(welcome, Response::Welcome { .. }));
+
+    // the first Player fills a column from the left while the second
+    // Player stacks up on the right, so the first Player's fourth piece wins
+    let moves = [
+        (Player::First, 0, "Left"),
+        (Player::Second, 0, "Right"),
+        (Player::First, 1, "Left"),
+        (Player::Second, 1, "Right"),
+        (Player::First, 2, "Left"),
+        (Player::Second, 2, "Right"),
+        (Player::First, 3, "Left"),
+    ];
+    let mut last_ack = None;
+    for (source, row, side) in moves {
+        client.send(&turn(source, row, side)).await;
+        last_ack = Some(
+            client
+                .recv_until(|r| matches!(r, Response::Acknowledged { .. }))
+                .await,
+        );
+    }
+
+    assert!(matches!(
+        last_ack,
+        Some(Response::Acknowledged {
+            game_over: Some(Some(Player::First)),
+            ..
+        })
+    ));
+    let state = state.lock().await;
+    assert_eq!(state.turns.len(), moves.len());
+    assert_eq!(state.result, Some(GameResult::Win(Player::First)));
+}
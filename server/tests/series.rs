@@ -0,0 +1,44 @@
+mod common;
+
+use common::{game, start};
+use server::{GameResult, Player, Request, Response};
+
+#[tokio::test]
+async fn best_of_three_ends_once_a_player_wins_two_games() {
+    let state = game(&["--best-of", "3"]);
+    let (mut first, mut second) = start(&state).await;
+
+    second.send(&Request::Resign).await;
+    assert_eq!(
+        first
+            .recv_until(|r| matches!(r, Response::NextGame { .. }))
+            .await,
+        Response::NextGame {
+            first_player: Player::Second,
+            scores: [1, 0],
+        }
+    );
+    second
+        .recv_until(|r| matches!(r, Response::NextGame { .. }))
+        .await;
+
+    // the second game is won the same way, which settles the match without
+    // a third
+    second.send(&Request::Resign).await;
+    for client in [&mut first, &mut second] {
+        assert_eq!(
+            client
+                .recv_until(|r| matches!(r, Response::MatchOver { .. }))
+                .await,
+            Response::MatchOver {
+                winner: Some(Player::First),
+                scores: [2, 0],
+            }
+        );
+    }
+    assert_eq!(
+        state.lock().await.result,
+        Some(GameResult::Resigned(Player::Second))
+    );
+    assert_eq!(state.lock().await.game, 2);
+}
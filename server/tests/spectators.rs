@@ -0,0 +1,69 @@
+mod common;
+
+use common::{game, start, turn, TestClient};
+use server::{Player, Request, Response};
+
+#[tokio::test]
+async fn spectator_past_the_limit_is_turned_away() {
+    let state = game(&["--spectator-limit", "1"]);
+    let (mut first, _second) = start(&state).await;
+    let (mut watching, _) = TestClient::open(&state, 3, &Request::Spectate).await;
+
+    let (mut extra, response) = TestClient::open(&state, 4, &Request::Spectate).await;
+    assert_eq!(response, Response::SpectatorFull);
+    assert!(extra.is_closed().await);
+    assert_eq!(state.lock().await.spectators.len(), 1);
+
+    // the spectator already watching still sees the game go on
+    first.send(&turn(Player::First, 0, "Left")).await;
+    assert!(matches!(
+        watching.recv().await,
+        Response::Turn(_) | Response::Played { .. }
+    ));
+}
+
+#[tokio::test]
+async fn observer_sees_the_game_but_cannot_play() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+    let mut observer = TestClient::observe(&state, 3);
+    assert!(matches!(observer.recv().await, Response::BoardState { .. }));
+
+    observer.send(&turn(Player::First, 0, "Left")).await;
+    assert_eq!(observer.try_recv().await, None);
+    assert_eq!(second.try_recv().await, None);
+    assert!(state.lock().await.turns.is_empty());
+
+    first.send(&turn(Player::First, 0, "Left")).await;
+    assert!(matches!(
+        observer.recv().await,
+        Response::Turn(_) | Response::Played { .. }
+    ));
+}
+
+#[tokio::test]
+async fn snapshot_shows_the_board_as_the_server_draws_it() {
+    let state = game(&[]);
+    let (mut first, _second) = start(&state).await;
+    first.send(&turn(Player::First, 0, "Left")).await;
+    first.recv().await;
+
+    let (mut watching, response) = TestClient::open(&state, 3, &Request::Spectate).await;
+    let snapshot = match response {
+        Response::BoardState { .. } => response,
+        _ => {
+            watching
+                .recv_until(|r| matches!(r, Response::BoardState { .. }))
+                .await
+        }
+    };
+
+    let state = state.lock().await;
+    match snapshot {
+        Response::BoardState { board, display, .. } => {
+            assert_eq!(display, state.board.to_string());
+            assert_eq!(board, state.board.encode());
+        }
+        response => panic!("expected a snapshot, got {:?}", response),
+    }
+}
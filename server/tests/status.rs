@@ -0,0 +1,26 @@
+mod common;
+
+use common::{game, TestClient};
+use server::{Request, Response};
+
+#[tokio::test]
+async fn status_counts_players_and_spectators() {
+    let state = game(&[]);
+    let (_player, _) = TestClient::join(&state, 1).await;
+    let (_first_spectator, snapshot) = TestClient::open(&state, 2, &Request::Spectate).await;
+    assert!(matches!(snapshot, Response::BoardState { .. }));
+    let (_second_spectator, _) = TestClient::open(&state, 3, &Request::Spectate).await;
+
+    let (mut poll, status) = TestClient::open(&state, 4, &Request::Status).await;
+    assert_eq!(
+        status,
+        Response::Status {
+            players: 1,
+            spectators: 2,
+            in_progress: false,
+            board: (7, 7),
+        }
+    );
+    assert!(poll.is_closed().await);
+    assert_eq!(state.lock().await.seats.len(), 1);
+}
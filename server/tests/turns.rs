@@ -0,0 +1,201 @@
+mod common;
+
+use common::{game, start, turn};
+use server::{ErrorCode, Player, Request, Response};
+
+#[tokio::test]
+async fn turn_on_behalf_of_other_player_is_rejected() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+
+    first.send(&turn(Player::Second, 0, "Left")).await;
+    match first.recv().await {
+        Response::Error { code, .. } => assert_eq!(code, ErrorCode::NotYourTurn),
+        response => panic!("expected an error, got {:?}", response),
+    }
+    assert_eq!(second.try_recv().await, None);
+    assert!(state.lock().await.turns.is_empty());
+
+    // the Player can still take their own Turn afterwards
+    first.send(&turn(Player::First, 0, "Left")).await;
+    match first.recv().await {
+        Response::Acknowledged { next_player, .. } => assert_eq!(next_player, Player::Second),
+        response => panic!("expected an acknowledgement, got {:?}", response),
+    }
+    assert!(matches!(
+        second.recv().await,
+        Response::Turn(_) | Response::Played { .. }
+    ));
+    assert_eq!(state.lock().await.turns.len(), 1);
+}
+
+#[tokio::test]
+async fn acknowledgement_says_where_the_piece_landed_and_who_is_next() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+    let acknowledged = |r: &Response| matches!(r, Response::Acknowledged { .. });
+
+    first.send(&turn(Player::First, 0, "Left")).await;
+    first.recv_until(acknowledged).await;
+    second.send(&turn(Player::Second, 0, "Right")).await;
+    second.recv_until(acknowledged).await;
+
+    // the Row already holds a piece at each end
+    first.send(&turn(Player::First, 0, "Left")).await;
+    assert_eq!(
+        first.recv_until(acknowledged).await,
+        Response::Acknowledged {
+            coordinate: (0, 5),
+            game_over: None,
+            next_player: Player::Second,
+            board: None,
+        }
+    );
+
+    second.send(&turn(Player::Second, 0, "Right")).await;
+    assert_eq!(
+        second.recv_until(acknowledged).await,
+        Response::Acknowledged {
+            coordinate: (0, 1),
+            game_over: None,
+            next_player: Player::First,
+            board: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn only_a_thin_client_is_sent_the_board() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+    let acknowledged = |r: &Response| matches!(r, Response::Acknowledged { .. });
+
+    first.send(&Request::ThinClient).await;
+    first.send(&turn(Player::First, 0, "Left")).await;
+    match first.recv_until(acknowledged).await {
+        Response::Acknowledged { board, .. } => {
+            assert_eq!(board, Some(state.lock().await.board.to_string()))
+        }
+        response => panic!("expected an acknowledgement, got {:?}", response),
+    }
+
+    second.send(&turn(Player::Second, 0, "Right")).await;
+    let ack = second.recv_until(acknowledged).await;
+    assert!(matches!(ack, Response::Acknowledged { board: None, .. }));
+    // the field is left out altogether rather than sent empty
+    assert!(!serde_json::to_string(&ack).unwrap().contains("board"));
+}
+
+#[tokio::test]
+async fn move_off_the_board_is_an_invalid_move() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+
+    first.send(&turn(Player::First, 9, "Left")).await;
+    match first.recv().await {
+        Response::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidMove),
+        response => panic!("expected an error, got {:?}", response),
+    }
+    assert_eq!(second.try_recv().await, None);
+    assert!(state.lock().await.turns.is_empty());
+}
+
+#[tokio::test]
+async fn rejected_move_leaves_the_same_player_to_move() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+
+    first.send(&turn(Player::First, 9, "Left")).await;
+    first.recv().await;
+    assert_eq!(state.lock().await.current_player, Player::First);
+
+    // the other Player still has to wait, and the Player who made the
+    // mistake can try again
+    second.send(&turn(Player::Second, 0, "Right")).await;
+    match second.recv().await {
+        Response::Error { code, .. } => assert_eq!(code, ErrorCode::NotYourTurn),
+        response => panic!("expected an error, got {:?}", response),
+    }
+    first.send(&turn(Player::First, 0, "Left")).await;
+    match first.recv().await {
+        Response::Acknowledged { next_player, .. } => assert_eq!(next_player, Player::Second),
+        response => panic!("expected an acknowledgement, got {:?}", response),
+    }
+}
+
+#[tokio::test]
+async fn turns_sent_at_the_same_time_are_played_one_at_a_time() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+
+    // each Player fires off a Turn at once, without waiting to be told
+    // whose turn it is
+    for row in 0..3 {
+        let (mine, theirs) = (
+            turn(Player::First, row, "Left"),
+            turn(Player::Second, row, "Right"),
+        );
+        tokio::join!(first.send(&mine), second.send(&theirs));
+    }
+    // let the server get through them all
+    while first.try_recv().await.is_some() || second.try_recv().await.is_some() {}
+
+    let state = state.lock().await;
+    let sources = state
+        .turns
+        .iter()
+        .map(|turn| serde_json::to_value(turn).unwrap()["source"].clone())
+        .collect::<Vec<_>>();
+    let alternating = ["First", "Second"]
+        .iter()
+        .cycle()
+        .take(sources.len())
+        .map(|player| serde_json::json!(player))
+        .collect::<Vec<_>>();
+    assert!(!sources.is_empty());
+    assert_eq!(sources, alternating);
+    let pieces = state
+        .board
+        .encode()
+        .chars()
+        .filter(|c| *c == 'X' || *c == 'O')
+        .count();
+    assert_eq!(pieces, state.turns.len());
+}
+
+#[tokio::test]
+async fn turns_sent_back_to_back_only_play_the_first() {
+    let state = game(&[]);
+    let (mut first, mut second) = start(&state).await;
+
+    // the first Player doesn't wait to hear back before sending more
+    for row in 0..3 {
+        first.send(&turn(Player::First, row, "Left")).await;
+    }
+
+    assert!(matches!(
+        first.recv().await,
+        Response::Acknowledged {
+            next_player: Player::Second,
+            ..
+        }
+    ));
+    for _ in 1..3 {
+        match first.recv().await {
+            Response::Error { code, .. } => assert_eq!(code, ErrorCode::NotYourTurn),
+            response => panic!("expected an error, got {:?}", response),
+        }
+    }
+    assert_eq!(state.lock().await.turns.len(), 1);
+
+    // the other Player is only told about the Turn that was played, and
+    // can answer it
+    assert!(matches!(
+        second.recv().await,
+        Response::Turn(_) | Response::Played { .. }
+    ));
+    assert_eq!(second.try_recv().await, None);
+    second.send(&turn(Player::Second, 0, "Right")).await;
+    assert!(matches!(second.recv().await, Response::Acknowledged { .. }));
+    assert_eq!(state.lock().await.turns.len(), 2);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_util::codec::{Framed, LinesCodec};
+
+use common::{connect_tcp, free_addr, send_tcp, spawn_server, turn};
+use server::{Player, Request, Response};
+
+/// Skip the server's Responses until one matches the given predicate, and
+/// return it.
+async fn recv_until(
+    lines: &mut Framed<TcpStream, LinesCodec>,
+    wanted: impl Fn(&Response) -> bool,
+) -> Response {
+    loop {
+        let line = lines.next().await.unwrap().unwrap();
+        let response = serde_json::from_str(&line).unwrap();
+        if wanted(&response) {
+            return response;
+        }
+    }
+}
+
+#[tokio::test]
+async fn played_turn_is_logged_with_where_it_landed() {
+    let addr = free_addr();
+    let (_server, mut log) = spawn_server(
+        Path::new("."),
+        &[
+            "start",
+            "-a",
+            &addr.to_string(),
+            "--no-persist",
+            "--verbose-turns",
+        ],
+    );
+
+    let mut first = connect_tcp(addr).await;
+    send_tcp(&mut first, &Request::Join).await;
+    recv_until(&mut first, |r| matches!(r, Response::Welcome { .. })).await;
+    let mut second = connect_tcp(addr).await;
+    send_tcp(&mut second, &Request::Join).await;
+    recv_until(&mut first, |r| *r == Response::GameStart).await;
+
+    send_tcp(&mut first, &turn(Player::First, 2, "Left")).await;
+    let coordinate =
+        match recv_until(&mut first, |r| matches!(r, Response::Acknowledged { .. })).await {
+            Response::Acknowledged { coordinate, .. } => coordinate,
+            _ => unreachable!(),
+        };
+
+    let expected = format!(
+        "game=1 ply=1 First 2L -> ({},{})",
+        coordinate.0, coordinate.1
+    );
+    let logged = timeout(Duration::from_secs(5), async {
+        while let Some(line) = log.next_line().await.unwrap() {
+            if line.starts_with("game=") {
+                return line;
+            }
+        }
+        panic!("the server stopped logging");
+    })
+    .await
+    .expect("timed out waiting for the Turn to be logged");
+
+    assert_eq!(logged, expected);
+    assert_eq!(coordinate, (2, 6));
+}
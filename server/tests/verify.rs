@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection};
+
+use common::spawn_server;
+use server::create_tables;
+
+/// Set up a directory for the server to run from whose database holds
+/// games with the given Turns, returning the directory the database is in
+/// and the one the server runs from.
+fn saved_games(name: &str, games: &[&str]) -> (PathBuf, PathBuf) {
+    // the server keeps its database in `../db`
+    let root = std::env::temp_dir().join(format!(
+        "sidestacker-verify-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let workdir = root.join("server");
+    fs::create_dir_all(&workdir).unwrap();
+    fs::create_dir_all(root.join("db")).unwrap();
+
+    let connection = Connection::open(root.join("db").join("games.db")).unwrap();
+    create_tables(&connection);
+    for turns in games {
+        connection
+            .execute("INSERT INTO games (turns) VALUES (?1)", params![turns])
+            .unwrap();
+    }
+
+    (root, workdir)
+}
+
+#[tokio::test]
+async fn verify_succeeds_when_every_game_holds_up() {
+    let (root, workdir) = saved_games(
+        "clean",
+        &[r#"[{"source":"First","mov":{"side":"Left","row":0}}]"#],
+    );
+
+    let (mut server, _log) = spawn_server(&workdir, &["verify"]);
+    let status = server.wait().await.unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn verify_fails_when_a_game_doesnt_hold_up() {
+    let (root, workdir) = saved_games(
+        "corrupt",
+        &[
+            r#"[{"source":"First","mov":{"side":"Left","row":0}}]"#,
+            "not turns at all",
+        ],
+    );
+
+    let (mut server, mut log) = spawn_server(&workdir, &["verify"]);
+    let mut lines = Vec::new();
+    while let Some(line) = log.next_line().await.unwrap() {
+        lines.push(line);
+    }
+    let status = server.wait().await.unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(!status.success());
+    assert!(lines.contains(&"Checked 2 games, 1 with problems.".to_string()));
+}